@@ -0,0 +1,95 @@
+use std::{cell::RefCell, rc::Rc};
+
+use super::{value::Key, RuntimeError, Value};
+use crate::parse::Span;
+
+/// A function, that is written in Rust, not in the language.
+/// They are declared in the global scope, so they can be shadowed like any other variable.
+pub struct Builtin {
+    pub name: &'static str,
+    pub arity: usize,
+    /// Gets exactly `arity` arguments, the caller checks that.
+    pub function: fn(Vec<Value>, Span) -> Result<Value, RuntimeError>,
+}
+impl std::fmt::Debug for Builtin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<builtin {}>", self.name)
+    }
+}
+pub static BUILTINS: &[Builtin] = &[
+    Builtin {
+        name: "len",
+        arity: 1,
+        function: len,
+    },
+    Builtin {
+        name: "keys",
+        arity: 1,
+        function: keys,
+    },
+    Builtin {
+        name: "values",
+        arity: 1,
+        function: values,
+    },
+    Builtin {
+        name: "remove",
+        arity: 2,
+        function: remove,
+    },
+];
+fn wrong_argument(
+    name: &'static str,
+    expected: &'static str,
+    found: &Value,
+    span: Span,
+) -> RuntimeError {
+    RuntimeError::ArgumentType {
+        name,
+        expected,
+        found: found.type_name(),
+        span: span.into(),
+    }
+}
+fn list(items: Vec<Value>) -> Value {
+    Value::List(Rc::new(RefCell::new(items)))
+}
+/// The number of items in a list or map, or the number of characters in a string.
+fn len(arguments: Vec<Value>, span: Span) -> Result<Value, RuntimeError> {
+    let length = match &arguments[0] {
+        Value::List(list) => list.borrow().len(),
+        Value::Map(map) => map.borrow().len(),
+        Value::String(string) => string.chars().count(),
+        value => return Err(wrong_argument("len", "a list, map or string", value, span)),
+    };
+    Ok(Value::Integer(length as i64))
+}
+/// The keys of the map in a new list, in the order they were added.
+fn keys(arguments: Vec<Value>, span: Span) -> Result<Value, RuntimeError> {
+    match &arguments[0] {
+        Value::Map(map) => Ok(list(
+            map.borrow().keys().cloned().map(Value::from).collect(),
+        )),
+        value => Err(wrong_argument("keys", "a map", value, span)),
+    }
+}
+/// The values of the map in a new list, in the same order as `keys`.
+fn values(arguments: Vec<Value>, span: Span) -> Result<Value, RuntimeError> {
+    match &arguments[0] {
+        Value::Map(map) => Ok(list(map.borrow().values().cloned().collect())),
+        value => Err(wrong_argument("values", "a map", value, span)),
+    }
+}
+/// Takes the key out of the map, and gives back its value.
+/// The order of the other keys stays the same.
+fn remove(arguments: Vec<Value>, span: Span) -> Result<Value, RuntimeError> {
+    let Value::Map(map) = &arguments[0] else {
+        return Err(wrong_argument("remove", "a map", &arguments[0], span));
+    };
+    let key = Key::new(&arguments[1], span)?;
+    let removed = map.borrow_mut().shift_remove(&key);
+    removed.ok_or_else(|| RuntimeError::MissingKey {
+        key: key.to_string(),
+        span: span.into(),
+    })
+}
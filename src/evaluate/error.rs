@@ -0,0 +1,149 @@
+use miette::{Diagnostic, SourceSpan};
+use thiserror::Error;
+
+use crate::parse::Operator;
+
+/// Everything that can go wrong while running a program.
+/// The spans point into the code, that was run, so miette can show where it happened.
+#[derive(Error, Debug, Diagnostic)]
+pub enum RuntimeError {
+    #[error("Can't use {operator} on {lhs} and {rhs}.")]
+    Operands {
+        operator: Operator,
+        lhs: &'static str,
+        rhs: &'static str,
+        #[label("This bit here")]
+        span: SourceSpan,
+    },
+    #[error("Can't use {operator} on {operand}.")]
+    Operand {
+        operator: Operator,
+        operand: &'static str,
+        #[label("This bit here")]
+        span: SourceSpan,
+    },
+    #[error("The numbers are too big for {operator}.")]
+    Overflow {
+        operator: Operator,
+        #[label("This bit here")]
+        span: SourceSpan,
+    },
+    #[error("Can't shift by a negative number.")]
+    NegativeShift {
+        #[label("This bit here")]
+        span: SourceSpan,
+    },
+    #[error("Negative numbers don't have a factorial.")]
+    NegativeFactorial {
+        #[label("This bit here")]
+        span: SourceSpan,
+    },
+    #[error("Division by zero.")]
+    DivisionByZero {
+        #[label("This bit here")]
+        span: SourceSpan,
+    },
+    #[error("The operands of {operator} have to be booleans, not {found}.")]
+    Logical {
+        operator: Operator,
+        found: &'static str,
+        #[label("This bit here")]
+        span: SourceSpan,
+    },
+    #[error("The condition has to be a boolean, not {found}.")]
+    Condition {
+        found: &'static str,
+        #[label("This bit here")]
+        span: SourceSpan,
+    },
+    #[error("{name} is not defined.")]
+    Undefined {
+        name: String,
+        #[label("This bit here")]
+        span: SourceSpan,
+    },
+    #[error("{name} is not declared.")]
+    #[diagnostic(help("Declare it first with `let {name}`."))]
+    AssignBeforeDeclare {
+        name: String,
+        #[label("This bit here")]
+        span: SourceSpan,
+    },
+    #[error("Only variables can be declared with let.")]
+    BadDeclaration {
+        #[label("This bit here")]
+        span: SourceSpan,
+    },
+    #[error("Can't call {found}, only functions.")]
+    NotCallable {
+        found: &'static str,
+        #[label("This bit here")]
+        span: SourceSpan,
+    },
+    #[error("Can't index into {found}, only lists and maps.")]
+    NotIndexable {
+        found: &'static str,
+        #[label("This bit here")]
+        span: SourceSpan,
+    },
+    #[error("The index has to be an integer, not {found}.")]
+    IndexType {
+        found: &'static str,
+        #[label("This bit here")]
+        span: SourceSpan,
+    },
+    #[error("The index can't be negative, but it is {index}.")]
+    NegativeIndex {
+        index: String,
+        #[label("This bit here")]
+        span: SourceSpan,
+    },
+    #[error("The index is {index}, but the length is only {length}.")]
+    OutOfBounds {
+        index: String,
+        length: usize,
+        #[label("This bit here")]
+        span: SourceSpan,
+    },
+    #[error("Can't use {found} as a map key, only integers, strings and booleans.")]
+    KeyType {
+        found: &'static str,
+        #[label("This bit here")]
+        span: SourceSpan,
+    },
+    #[error("The map has no key {key}.")]
+    MissingKey {
+        key: String,
+        #[label("This bit here")]
+        span: SourceSpan,
+    },
+    #[error("{name} needs {expected}, not {found}.")]
+    ArgumentType {
+        name: &'static str,
+        expected: &'static str,
+        found: &'static str,
+        #[label("This bit here")]
+        span: SourceSpan,
+    },
+    #[error("Can't loop over {found}, only lists, maps, strings and ranges.")]
+    NotIterable {
+        found: &'static str,
+        #[label("This bit here")]
+        span: SourceSpan,
+    },
+    #[error("Too many calls inside each other, the limit is {limit}.")]
+    #[diagnostic(help("Is there a recursion without an end?"))]
+    RecursionLimit {
+        limit: usize,
+        #[label("This bit here")]
+        span: SourceSpan,
+    },
+    #[error("{name} takes {expected} arguments, but got {found}.")]
+    Arity {
+        name: String,
+        expected: usize,
+        found: usize,
+        #[label("This bit here")]
+        span: SourceSpan,
+    },
+}
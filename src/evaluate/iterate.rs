@@ -0,0 +1,60 @@
+use std::{ops, rc::Rc, vec};
+
+use super::{
+    value::{Key, List},
+    RuntimeError, Value,
+};
+use crate::parse::Span;
+
+/// The items of a value, one at a time, this is what a `for` loop walks.
+/// A list is read again for every item, so the body can change it, and the loop sees the change.
+/// A map gives its keys, they are copied at the start, so removing one in the body is fine.
+pub enum Iter {
+    List(List, usize),
+    Keys(vec::IntoIter<Key>),
+    /// The byte offset of the next character.
+    String(Rc<str>, usize),
+    Range(ops::Range<i64>),
+    RangeInclusive(ops::RangeInclusive<i64>),
+}
+impl Iter {
+    /// The span is only for the error.
+    pub fn new(value: Value, span: Span) -> Result<Iter, RuntimeError> {
+        match value {
+            Value::List(list) => Ok(Iter::List(list, 0)),
+            Value::Map(map) => Ok(Iter::Keys(
+                map.borrow().keys().cloned().collect::<Vec<_>>().into_iter(),
+            )),
+            Value::String(string) => Ok(Iter::String(string, 0)),
+            Value::Range(range) if range.inclusive => {
+                Ok(Iter::RangeInclusive(range.start..=range.end))
+            }
+            Value::Range(range) => Ok(Iter::Range(range.start..range.end)),
+            _ => Err(RuntimeError::NotIterable {
+                found: value.type_name(),
+                span: span.into(),
+            }),
+        }
+    }
+}
+impl Iterator for Iter {
+    type Item = Value;
+
+    fn next(&mut self) -> Option<Value> {
+        match self {
+            Iter::List(list, index) => {
+                let item = list.borrow().get(*index).cloned()?;
+                *index += 1;
+                Some(item)
+            }
+            Iter::Keys(keys) => keys.next().map(Value::from),
+            Iter::String(string, offset) => {
+                let character = string[*offset..].chars().next()?;
+                *offset += character.len_utf8();
+                Some(Value::String(character.to_string().into()))
+            }
+            Iter::Range(range) => range.next().map(Value::Integer),
+            Iter::RangeInclusive(range) => range.next().map(Value::Integer),
+        }
+    }
+}
@@ -1,103 +1,1450 @@
 pub struct Evaluator {
-    table: HashMap<String, Box<dyn Any>>,
+    scope: Rc<RefCell<Scope>>,
+    /// How many function calls are running right now, one inside the other.
+    depth: usize,
 }
-use std::{any::Any, collections::HashMap, ops::Add};
+/// The most calls, that can be inside each other, deeper recursion is an error,
+/// instead of overflowing the stack, which would kill the whole process.
+/// It leaves room on a default 8 MiB stack, even in a debug build,
+/// where a call in a script takes about 11 KiB of it.
+pub const MAX_DEPTH: usize = 256;
+use std::{cell::RefCell, rc::Rc};
 
-use crate::parse::{self, *};
+use indexmap::IndexMap;
+use num_traits::Signed;
+
+use crate::parse::*;
+mod builtins;
+mod error;
+mod iterate;
 mod operations;
+mod scope;
+mod value;
+use builtins::BUILTINS;
+pub use error::RuntimeError;
+use iterate::Iter;
 use operations::*;
+use scope::Scope;
+pub use value::{Function, Key, List, Map, Range, Value};
+/// Stops the evaluation of the tree, and goes up until something catches it.
+/// A `return` is caught by the function call, a `break` and `continue` by their loop,
+/// errors go all the way up.
+#[derive(Debug)]
+enum Unwind {
+    Error(RuntimeError),
+    Return(Value),
+    /// The label of the loop, or `None` for the innermost one.
+    Break(Option<Rc<str>>),
+    Continue(Option<Rc<str>>),
+}
+impl Unwind {
+    /// Catches the `break` and `continue`, that belong to the loop with this label,
+    /// the rest keep going up. Tells whether the loop goes on.
+    fn looping(result: Result<Value, Unwind>, label: &Option<Rc<str>>) -> Result<bool, Unwind> {
+        let ours = |target: &Option<Rc<str>>| target.is_none() || target == label;
+        match result {
+            Ok(_) => Ok(true),
+            Err(Unwind::Continue(target)) if ours(&target) => Ok(true),
+            Err(Unwind::Break(target)) if ours(&target) => Ok(false),
+            Err(unwind) => Err(unwind),
+        }
+    }
+}
+impl From<RuntimeError> for Unwind {
+    fn from(error: RuntimeError) -> Self {
+        Unwind::Error(error)
+    }
+}
+/// Somewhere a value can be stored. It is found before the new value is computed,
+/// so in `a[f()] += 1` the `f` is only called once.
+enum Place {
+    Variable(Rc<str>),
+    Element(List, usize),
+    /// The key doesn't have to be in the map yet, assigning to it adds it.
+    Entry(Map, Key),
+}
+/// Why an operation failed. It doesn't know where it happened,
+/// the evaluator turns it into a `RuntimeError` with the location.
 #[derive(Debug)]
 pub enum EvalErr {
     CantAdd,
     WrongType,
-    CantCompare
+    CantCompare,
+    /// The number is too big for what it is used for.
+    Overflow,
+    DivisionByZero,
+    NegativeShift,
+    NegativeFactorial,
 }
-pub enum ValueTypes {
-    Float(f32),
-    Integer(i32),
-    String(String),
-    Boolean(bool),
+impl EvalErr {
+    /// Overflow, division by zero, a negative shift and a negative factorial have their own errors,
+    /// the rest mean that the types were wrong, that one is made by `wrong_types`,
+    /// because it depends on the operands.
+    fn at(
+        self,
+        operator: Operator,
+        span: Span,
+        wrong_types: impl FnOnce() -> RuntimeError,
+    ) -> Unwind {
+        match self {
+            EvalErr::Overflow => RuntimeError::Overflow {
+                operator,
+                span: span.into(),
+            },
+            EvalErr::DivisionByZero => RuntimeError::DivisionByZero { span: span.into() },
+            EvalErr::NegativeShift => RuntimeError::NegativeShift { span: span.into() },
+            EvalErr::NegativeFactorial => RuntimeError::NegativeFactorial { span: span.into() },
+            EvalErr::CantAdd | EvalErr::WrongType | EvalErr::CantCompare => wrong_types(),
+        }
+        .into()
+    }
 }
 
 impl Evaluator {
-    pub fn new() -> Evaluator{
-        Evaluator {
-            table: HashMap::new()
+    pub fn new() -> Evaluator {
+        let scope = Scope::new();
+        for builtin in BUILTINS {
+            scope
+                .borrow_mut()
+                .declare(builtin.name, Value::Builtin(builtin));
+        }
+        Evaluator { scope, depth: 0 }
+    }
+    /// Conditions have to be booleans, there is no truthiness.
+    fn eval_condition(&mut self, condition: &TokenTree) -> Result<bool, Unwind> {
+        let span = condition.span();
+        match self.evaluate(condition)? {
+            Value::Boolean(boolean) => Ok(boolean),
+            value => Err(RuntimeError::Condition {
+                found: value.type_name(),
+                span: span.into(),
+            }
+            .into()),
+        }
+    }
+    /// `&&` and `||` only evaluate the right side, if the left one didn't decide it already.
+    /// Both sides have to be booleans, but the right one is not checked, if it was skipped.
+    fn eval_logical(
+        &mut self,
+        operator: Operator,
+        lhs: &TokenTree,
+        rhs: &TokenTree,
+    ) -> Result<Value, Unwind> {
+        let decided = matches!(operator, Operator::Or);
+        if self.eval_operand(operator, lhs)? == decided {
+            return Ok(Value::Boolean(decided));
+        }
+        Ok(Value::Boolean(self.eval_operand(operator, rhs)?))
+    }
+    fn eval_operand(&mut self, operator: Operator, tree: &TokenTree) -> Result<bool, Unwind> {
+        let span = tree.span();
+        match self.evaluate(tree)? {
+            Value::Boolean(boolean) => Ok(boolean),
+            value => Err(RuntimeError::Logical {
+                operator,
+                found: value.type_name(),
+                span: span.into(),
+            }
+            .into()),
         }
     }
-    fn eval_if(tree: TokenTree) {
-        if let TokenTree::If {
+    /// The value of an if is the value of the branch that ran,
+    /// or nil if the condition was false and there is no else.
+    fn eval_if(&mut self, tree: &TokenTree) -> Result<Value, Unwind> {
+        let TokenTree::If {
             condition,
             positive,
             negative,
+            ..
         } = tree
-        {}
+        else {
+            unreachable!("Only called with an if.")
+        };
+        if self.eval_condition(condition)? {
+            self.evaluate(positive)
+        } else if let Some(negative) = negative {
+            self.evaluate(negative)
+        } else {
+            Ok(Value::Nil)
+        }
     }
-    pub fn evaluate<'a>(&mut self, tree: TokenTree<'a>) -> Option<Atomic<'a>> {
-        //println!("{}", tree);
-        match tree {
-            TokenTree::Atomic(val) => return Some(val),
-            TokenTree::InfixExpression(op, arr) => {
-                let lhs = self.evaluate(arr[0].clone())?;
-                let rhs = self.evaluate(arr[1].clone())?;
-                let op_result = match op {
-                    Operator::Plus => add_together(lhs, rhs),
-                    Operator::Minus => sub_together(lhs, rhs),
-                    Operator::Star => mul_together(lhs, rhs),
-                    Operator::Slash => div_together(lhs, rhs),
-
-                    Operator::Less => less_than_together(lhs, rhs),
-                    Operator::LessOrEqual => lessequal_than_together(lhs, rhs),
-                    Operator::EqualEqual => equal_with_together(lhs, rhs),
-                    Operator::NotEqual => notequal_with_together(lhs, rhs),
-                    Operator::Greater => greater_than_together(lhs, rhs),
-                    Operator::GreaterOrEqual => greaterequal_than_together(lhs, rhs),
-
-                    Operator::And => and_together(lhs, rhs),
-                    Operator::Or => or_together(lhs, rhs),
+    /// A while loop is always nil, it is only there for its side effects.
+    fn eval_while(
+        &mut self,
+        label: &Option<Rc<str>>,
+        condition: &TokenTree,
+        body: &TokenTree,
+    ) -> Result<Value, Unwind> {
+        while self.eval_condition(condition)? {
+            let result = self.evaluate(body);
+            if !Unwind::looping(result, label)? {
+                break;
+            }
+        }
+        Ok(Value::Nil)
+    }
+    /// Every round gets a new scope with the loop variable in it,
+    /// so a closure made in the body keeps the item of its own round.
+    fn eval_for(
+        &mut self,
+        label: &Option<Rc<str>>,
+        variable: &str,
+        iterable: &TokenTree,
+        body: &TokenTree,
+    ) -> Result<Value, Unwind> {
+        let span = iterable.span();
+        let items = Iter::new(self.evaluate(iterable)?, span)?;
+        for item in items {
+            let parent = self.scope.clone();
+            self.scope = Scope::child(parent.clone());
+            self.scope.borrow_mut().declare(variable, item);
+            let result = self.evaluate(body);
+            self.scope = parent;
+            if !Unwind::looping(result, label)? {
+                break;
+            }
+        }
+        Ok(Value::Nil)
+    }
+    /// Runs the statements of a block in a new scope, that is thrown away at the end,
+    /// so the variables declared inside are not visible after it.
+    fn eval_block(&mut self, statements: &[Statement]) -> Result<Value, Unwind> {
+        let parent = self.scope.clone();
+        self.scope = Scope::child(parent.clone());
+        let result = self.statements(statements);
+        self.scope = parent;
+        result
+    }
+    /// Runs every statement in order, the value is the value of the
+    /// last statement, if that one is not closed by a `;`.
+    fn statements(&mut self, statements: &[Statement]) -> Result<Value, Unwind> {
+        let mut value = Value::Nil;
+        for statement in statements {
+            value = match statement {
+                Statement::Semi(tree) => {
+                    self.evaluate(tree)?;
+                    Value::Nil
+                }
+                Statement::Expression(tree) => self.evaluate(tree)?,
+            };
+        }
+        Ok(value)
+    }
+    /// Runs the program in the global scope, so the REPL keeps its variables between the lines.
+    /// A `return` outside of a function stops the program, and its value is the program's.
+    pub fn run(&mut self, program: Program) -> Result<Value, RuntimeError> {
+        match self.statements(&program.statements) {
+            Ok(value) | Err(Unwind::Return(value)) => Ok(value),
+            Err(Unwind::Error(error)) => Err(error),
+            Err(Unwind::Break(_) | Unwind::Continue(_)) => {
+                unreachable!("The parser only allows them inside of loops.")
+            }
+        }
+    }
+    /// Calls the function in a new scope, that is the child of the one it was declared in,
+    /// not the caller's, so it only sees its own variables and the ones around its declaration.
+    fn call(&mut self, callee: Value, arguments: Vec<Value>, span: Span) -> Result<Value, Unwind> {
+        let function = match callee {
+            Value::Function(function) => function,
+            Value::Builtin(builtin) => {
+                if builtin.arity != arguments.len() {
+                    return Err(RuntimeError::Arity {
+                        name: builtin.name.to_string(),
+                        expected: builtin.arity,
+                        found: arguments.len(),
+                        span: span.into(),
+                    }
+                    .into());
+                }
+                return Ok((builtin.function)(arguments, span)?);
+            }
+            _ => {
+                return Err(RuntimeError::NotCallable {
+                    found: callee.type_name(),
+                    span: span.into(),
+                }
+                .into())
+            }
+        };
+        if function.parameters.len() != arguments.len() {
+            return Err(RuntimeError::Arity {
+                name: function
+                    .name
+                    .as_deref()
+                    .unwrap_or("The closure")
+                    .to_string(),
+                expected: function.parameters.len(),
+                found: arguments.len(),
+                span: span.into(),
+            }
+            .into());
+        }
+        if self.depth == MAX_DEPTH {
+            return Err(RuntimeError::RecursionLimit {
+                limit: MAX_DEPTH,
+                span: span.into(),
+            }
+            .into());
+        }
+        let scope = Scope::child(function.scope.clone());
+        for (parameter, argument) in function.parameters.iter().zip(arguments) {
+            scope.borrow_mut().declare(parameter, argument);
+        }
 
-                    _ => unreachable!("This is all the possibilites.")
-                };
-                if let Ok(atomic) = op_result {
-                    return Some(atomic);
+        let caller = std::mem::replace(&mut self.scope, scope);
+        self.depth += 1;
+        let result = self.evaluate(&function.body);
+        self.depth -= 1;
+        self.scope = caller;
+        match result {
+            Err(Unwind::Return(value)) => Ok(value),
+            result => result,
+        }
+    }
+    /// Finds the place, that the parser already checked, is assignable.
+    fn place(&mut self, tree: &TokenTree) -> Result<Place, Unwind> {
+        match tree {
+            TokenTree::Atomic(Atomic::Identifier(name), _) => Ok(Place::Variable(name.clone())),
+            TokenTree::PostfixExpression(Operator::Index, arr, span) => {
+                let target = self.evaluate(&arr[0])?;
+                let index = self.evaluate(&arr[1])?;
+                self.element(target, index, *span)
+            }
+            _ => unreachable!("Left hand side is not assignable!"),
+        }
+    }
+    fn load(&self, place: &Place, span: Span) -> Result<Value, Unwind> {
+        match place {
+            Place::Variable(name) => self.scope.borrow().get(name).ok_or_else(|| {
+                RuntimeError::Undefined {
+                    name: name.to_string(),
+                    span: span.into(),
                 }
-                else if let Err(err) = op_result {
-                    panic!("{:?}", err);
+                .into()
+            }),
+            Place::Element(list, index) => Ok(list.borrow()[*index].clone()),
+            Place::Entry(map, key) => map.borrow().get(key).cloned().ok_or_else(|| {
+                RuntimeError::MissingKey {
+                    key: key.to_string(),
+                    span: span.into(),
                 }
-            },
-            TokenTree::PostfixExpression(op, arr) => {
-                let lhs = self.evaluate(arr[0].clone())?;
-                let op_result = match op {
-                    Operator::Bang => factor(lhs),
-                    _ => unreachable!("This is all the possibilites.")
-                };
-                if let Ok(atomic) = op_result {
-                    return Some(atomic);
+                .into()
+            }),
+        }
+    }
+    fn store(&mut self, place: Place, value: Value, span: Span) -> Result<(), Unwind> {
+        match place {
+            Place::Variable(name) => {
+                if !self.scope.borrow_mut().assign(&name, value) {
+                    return Err(RuntimeError::AssignBeforeDeclare {
+                        name: name.to_string(),
+                        span: span.into(),
+                    }
+                    .into());
                 }
-                else {
-                    panic!("TODO better error handling!")
+            }
+            Place::Element(list, index) => list.borrow_mut()[index] = value,
+            Place::Entry(map, key) => {
+                map.borrow_mut().insert(key, value);
+            }
+        }
+        Ok(())
+    }
+    /// The place of `target[index]`, so it can be read or written.
+    /// For a list, it checks that the element exists, a map key is only checked when it is read.
+    fn element(&self, target: Value, index: Value, span: Span) -> Result<Place, Unwind> {
+        let list = match target {
+            Value::List(list) => list,
+            Value::Map(map) => return Ok(Place::Entry(map, Key::new(&index, span)?)),
+            _ => {
+                return Err(RuntimeError::NotIndexable {
+                    found: target.type_name(),
+                    span: span.into(),
                 }
+                .into())
+            }
+        };
+        let length = list.borrow().len();
+        let negative = match &index {
+            Value::Integer(integer) => match usize::try_from(*integer) {
+                Ok(position) if position < length => return Ok(Place::Element(list, position)),
+                position => position.is_err(),
             },
-            TokenTree::PrefixExpression(op, arr) => {
-                let rhs = self.evaluate(arr[0].clone())?;
-                let op_result = match op {
-                    Operator::Plus => Ok(rhs),
-                    Operator::Minus => match rhs {
-                        Atomic::Float(float) => Ok(Atomic::Float(-float)),
-                        Atomic::Integer(integer) => Ok(Atomic::Integer(-integer)),
-                        _ => Err(EvalErr::WrongType)
-                    },
-                    _ => unreachable!("This is all the possibilites")
-                };
-                if let Ok(atomic) = op_result {
-                    return Some(atomic);
+            Value::BigInt(integer) => integer.is_negative(),
+            _ => {
+                return Err(RuntimeError::IndexType {
+                    found: index.type_name(),
+                    span: span.into(),
                 }
-                else {
-                    panic!("TODO better error handling!")
+                .into())
+            }
+        };
+        let index = index.to_string();
+        let error = if negative {
+            RuntimeError::NegativeIndex {
+                index,
+                span: span.into(),
+            }
+        } else {
+            RuntimeError::OutOfBounds {
+                index,
+                length,
+                span: span.into(),
+            }
+        };
+        Err(error.into())
+    }
+    /// The operators with two operands, the compound assignments use the same ones.
+    fn binary(&self, op: Operator, lhs: Value, rhs: Value, span: Span) -> Result<Value, Unwind> {
+        let (lhs_type, rhs_type) = (lhs.type_name(), rhs.type_name());
+        let op_result = match op {
+            Operator::Plus | Operator::PlusAssign => add_together(lhs, rhs),
+            Operator::Minus | Operator::MinusAssign => sub_together(lhs, rhs),
+            Operator::Star | Operator::StarAssign => mul_together(lhs, rhs),
+            Operator::Slash | Operator::SlashAssign => div_together(lhs, rhs),
+            Operator::IntDivide => int_div_together(lhs, rhs),
+            Operator::Modulo => rem_together(lhs, rhs),
+            Operator::Power => pow_together(lhs, rhs),
+
+            Operator::BitAnd => bitand_together(lhs, rhs),
+            Operator::BitOr => bitor_together(lhs, rhs),
+            Operator::BitXor => bitxor_together(lhs, rhs),
+            Operator::ShiftLeft => shl_together(lhs, rhs),
+            Operator::ShiftRight => shr_together(lhs, rhs),
+
+            Operator::Less => less_than_together(lhs, rhs),
+            Operator::LessOrEqual => lessequal_than_together(lhs, rhs),
+            Operator::EqualEqual => equal_with_together(lhs, rhs),
+            Operator::NotEqual => notequal_with_together(lhs, rhs),
+            Operator::Range => range_together(lhs, rhs, false),
+            Operator::RangeInclusive => range_together(lhs, rhs, true),
+            Operator::Greater => greater_than_together(lhs, rhs),
+            Operator::GreaterOrEqual => greaterequal_than_together(lhs, rhs),
+
+            _ => unreachable!("This is all the possibilites."),
+        };
+        op_result.map_err(|error| {
+            error.at(op, span, || RuntimeError::Operands {
+                operator: op,
+                lhs: lhs_type,
+                rhs: rhs_type,
+                span: span.into(),
+            })
+        })
+    }
+    /// `++` and `--` only work on numbers, they add or take away one.
+    fn step(&self, op: Operator, value: Value, span: Span) -> Result<Value, Unwind> {
+        let operand = value.type_name();
+        let op_result = match (op, value) {
+            (
+                Operator::Increment,
+                value @ (Value::Integer(_) | Value::BigInt(_) | Value::Float(_)),
+            ) => add_together(value, Value::Integer(1)),
+            (
+                Operator::Decrement,
+                value @ (Value::Integer(_) | Value::BigInt(_) | Value::Float(_)),
+            ) => sub_together(value, Value::Integer(1)),
+            _ => Err(EvalErr::WrongType),
+        };
+        op_result.map_err(|error| {
+            error.at(op, span, || RuntimeError::Operand {
+                operator: op,
+                operand,
+                span: span.into(),
+            })
+        })
+    }
+    /// Only picks the right method for the node. The work is done in them, so the frame of
+    /// this one stays small, every call in a script goes through it a few times.
+    fn evaluate(&mut self, tree: &TokenTree) -> Result<Value, Unwind> {
+        //println!("{}", tree);
+        match tree {
+            TokenTree::Atomic(Atomic::Identifier(name), span) => self.eval_variable(name, *span),
+            TokenTree::Atomic(val, _) => Ok(val.clone().into()),
+            TokenTree::List(items, _) => self.eval_list(items),
+            TokenTree::Map(entries, _) => self.eval_map(entries),
+            TokenTree::PostfixExpression(Operator::Index, arr, span) => {
+                self.eval_index(&arr[0], &arr[1], *span)
+            }
+            TokenTree::PrefixExpression(Operator::Let, arr, span) => self.eval_let(&arr[0], *span),
+            TokenTree::InfixExpression(
+                op @ (Operator::Assign
+                | Operator::PlusAssign
+                | Operator::MinusAssign
+                | Operator::StarAssign
+                | Operator::SlashAssign),
+                arr,
+                span,
+            ) => self.eval_assign(*op, &arr[0], &arr[1], *span),
+            TokenTree::InfixExpression(op @ (Operator::And | Operator::Or), arr, _) => {
+                self.eval_logical(*op, &arr[0], &arr[1])
+            }
+            TokenTree::InfixExpression(op, arr, span) => {
+                self.eval_binary(*op, &arr[0], &arr[1], *span)
+            }
+            TokenTree::PostfixExpression(Operator::Call, arr, span) => {
+                self.eval_call(&arr[0], &arr[1..], *span)
+            }
+            TokenTree::PostfixExpression(
+                op @ (Operator::Increment | Operator::Decrement),
+                arr,
+                span,
+            ) => self.eval_step(*op, &arr[0], false, *span),
+            TokenTree::PrefixExpression(
+                op @ (Operator::Increment | Operator::Decrement),
+                arr,
+                span,
+            ) => self.eval_step(*op, &arr[0], true, *span),
+            TokenTree::PostfixExpression(op, arr, span)
+            | TokenTree::PrefixExpression(op, arr, span) => self.eval_unary(*op, &arr[0], *span),
+            TokenTree::Function {
+                name,
+                parameters,
+                body,
+                ..
+            } => self.eval_function(name, parameters, body),
+            TokenTree::Block(statements, _) => self.eval_block(statements),
+            TokenTree::Return(value, _) => {
+                let value = match value {
+                    Some(value) => self.evaluate(value)?,
+                    None => Value::Nil,
+                };
+                Err(Unwind::Return(value))
+            }
+            TokenTree::If { .. } => self.eval_if(tree),
+            TokenTree::While {
+                label,
+                condition,
+                body,
+                ..
+            } => self.eval_while(label, condition, body),
+            TokenTree::For {
+                label,
+                variable,
+                iterable,
+                body,
+                ..
+            } => self.eval_for(label, variable, iterable, body),
+            TokenTree::Break(label, _) => Err(Unwind::Break(label.clone())),
+            TokenTree::Continue(label, _) => Err(Unwind::Continue(label.clone())),
+        }
+    }
+    fn eval_variable(&self, name: &str, span: Span) -> Result<Value, Unwind> {
+        self.scope.borrow().get(name).ok_or_else(|| {
+            RuntimeError::Undefined {
+                name: name.to_string(),
+                span: span.into(),
+            }
+            .into()
+        })
+    }
+    fn eval_list(&mut self, items: &[TokenTree]) -> Result<Value, Unwind> {
+        let items = items
+            .iter()
+            .map(|item| self.evaluate(item))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Value::List(Rc::new(RefCell::new(items))))
+    }
+    /// A key, that is there twice, keeps its first position, but gets the last value.
+    fn eval_map(&mut self, entries: &[(TokenTree, TokenTree)]) -> Result<Value, Unwind> {
+        let mut map = IndexMap::new();
+        for (key, value) in entries {
+            let span = key.span();
+            let key = Key::new(&self.evaluate(key)?, span)?;
+            map.insert(key, self.evaluate(value)?);
+        }
+        Ok(Value::Map(Rc::new(RefCell::new(map))))
+    }
+    fn eval_index(
+        &mut self,
+        target: &TokenTree,
+        index: &TokenTree,
+        span: Span,
+    ) -> Result<Value, Unwind> {
+        let target = self.evaluate(target)?;
+        let index = self.evaluate(index)?;
+        let place = self.element(target, index, span)?;
+        self.load(&place, span)
+    }
+    /// `let name = value`, or just `let name`, which starts out as nil.
+    fn eval_let(&mut self, declaration: &TokenTree, span: Span) -> Result<Value, Unwind> {
+        let (name, value) = match declaration {
+            TokenTree::Atomic(Atomic::Identifier(name), _) => (name.clone(), Value::Nil),
+            TokenTree::InfixExpression(Operator::Assign, pair, _) => match &pair[0] {
+                TokenTree::Atomic(Atomic::Identifier(name), _) => {
+                    (name.clone(), self.evaluate(&pair[1])?)
                 }
+                _ => return Err(RuntimeError::BadDeclaration { span: span.into() }.into()),
             },
-            _ => todo!()
+            _ => return Err(RuntimeError::BadDeclaration { span: span.into() }.into()),
+        };
+        self.scope.borrow_mut().declare(&name, value);
+        Ok(Value::Nil)
+    }
+    /// `a += 1` is `a = a + 1`, but the errors show the `+=`.
+    fn eval_assign(
+        &mut self,
+        op: Operator,
+        target: &TokenTree,
+        value: &TokenTree,
+        span: Span,
+    ) -> Result<Value, Unwind> {
+        let place = self.place(target)?;
+        let value = if op == Operator::Assign {
+            self.evaluate(value)?
+        } else {
+            let lhs = self.load(&place, span)?;
+            let rhs = self.evaluate(value)?;
+            self.binary(op, lhs, rhs, span)?
+        };
+        self.store(place, value.clone(), span)?;
+        Ok(value)
+    }
+    fn eval_binary(
+        &mut self,
+        op: Operator,
+        lhs: &TokenTree,
+        rhs: &TokenTree,
+        span: Span,
+    ) -> Result<Value, Unwind> {
+        let lhs = self.evaluate(lhs)?;
+        let rhs = self.evaluate(rhs)?;
+        self.binary(op, lhs, rhs, span)
+    }
+    /// The callee is first, then the arguments, they are evaluated from left to right.
+    fn eval_call(
+        &mut self,
+        callee: &TokenTree,
+        arguments: &[TokenTree],
+        span: Span,
+    ) -> Result<Value, Unwind> {
+        let callee = self.evaluate(callee)?;
+        let arguments = arguments
+            .iter()
+            .map(|argument| self.evaluate(argument))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.call(callee, arguments, span)
+    }
+    /// The prefix `++a` gives the new value, the postfix `a++` the old one.
+    fn eval_step(
+        &mut self,
+        op: Operator,
+        target: &TokenTree,
+        prefix: bool,
+        span: Span,
+    ) -> Result<Value, Unwind> {
+        let place = self.place(target)?;
+        let old = self.load(&place, span)?;
+        let new = self.step(op, old.clone(), span)?;
+        self.store(place, new.clone(), span)?;
+        Ok(if prefix { new } else { old })
+    }
+    /// The operators with one operand, that don't change a variable.
+    fn eval_unary(
+        &mut self,
+        op: Operator,
+        operand: &TokenTree,
+        span: Span,
+    ) -> Result<Value, Unwind> {
+        let value = self.evaluate(operand)?;
+        let operand = value.type_name();
+        let op_result = match op {
+            Operator::Bang => factor(value),
+            Operator::Plus => Ok(value),
+            Operator::Minus => negate(value),
+            Operator::BitNot => bit_not(value),
+            Operator::Not => match value {
+                Value::Boolean(boolean) => Ok(Value::Boolean(!boolean)),
+                _ => Err(EvalErr::WrongType),
+            },
+            _ => unreachable!("This is all the possibilites."),
+        };
+        op_result.map_err(|error| {
+            error.at(op, span, || RuntimeError::Operand {
+                operator: op,
+                operand,
+                span: span.into(),
+            })
+        })
+    }
+    /// A named function is a declaration, an anonymous one is just a value.
+    fn eval_function(
+        &mut self,
+        name: &Option<Rc<str>>,
+        parameters: &[Rc<str>],
+        body: &Rc<TokenTree>,
+    ) -> Result<Value, Unwind> {
+        let function = Value::Function(Rc::new(Function {
+            name: name.clone(),
+            parameters: parameters.to_vec(),
+            body: body.clone(),
+            scope: self.scope.clone(),
+        }));
+        match name {
+            Some(name) => {
+                self.scope.borrow_mut().declare(name, function);
+                Ok(Value::Nil)
+            }
+            None => Ok(function),
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::{Evaluator, RuntimeError, MAX_DEPTH};
+    use crate::parse::{Operator, Parser, Statement, TokenTree};
+
+    fn tree(input: &str) -> TokenTree {
+        match Parser::new(input).parse().unwrap().statements.remove(0) {
+            Statement::Semi(tree) | Statement::Expression(tree) => tree,
+        }
+    }
+
+    fn run(input: &str) -> Result<String, RuntimeError> {
+        let mut evaluator = Evaluator::new();
+        evaluator
+            .run(Parser::new(input).parse().unwrap())
+            .map(|value| value.to_string())
+    }
+    #[test]
+    fn variables() {
+        assert_eq!(run("let a = 2; let b = a * 3; a = b + 1; a").unwrap(), "7");
+        assert_eq!(run("let a; a").unwrap(), "()");
+    }
+    #[test]
+    fn redeclare() {
+        assert_eq!(run("let a = 1; let a = a + 1; a").unwrap(), "2");
+    }
+    #[test]
+    fn undefined_variable() {
+        assert!(matches!(
+            run("1 + a"),
+            Err(RuntimeError::Undefined { name, span }) if name == "a" && span.offset() == 4
+        ));
+        assert!(matches!(
+            run("a = 1"),
+            Err(RuntimeError::AssignBeforeDeclare { name, .. }) if name == "a"
+        ));
+    }
+    #[test]
+    fn compound_assignment() {
+        assert_eq!(
+            run("let a = 10; a += 5; a -= 1; a *= 3; a /= 2; a").unwrap(),
+            "21"
+        );
+        assert_eq!(run("let s = \"a\"; s += \"b\"").unwrap(), "ab");
+        assert_eq!(run("let a = 0; let b = 0; a = b += 3; a + b").unwrap(), "6");
+        // Postfix gives the old value, prefix the new one.
+        assert_eq!(run("let a = 1; let b = a++; b + a * 10").unwrap(), "21");
+        assert_eq!(run("let a = 1; let b = --a; b + a * 10").unwrap(), "0");
+        assert!(matches!(
+            run("let a = true; a++"),
+            Err(RuntimeError::Operand {
+                operand: "boolean",
+                ..
+            })
+        ));
+        assert!(matches!(
+            run("let a = 1; a += \"b\""),
+            Err(RuntimeError::Operands {
+                lhs: "integer",
+                rhs: "string",
+                ..
+            })
+        ));
+        assert!(matches!(
+            run("b += 1"),
+            Err(RuntimeError::Undefined { name, .. }) if name == "b"
+        ));
+    }
+    #[test]
+    fn not_and_factorial() {
+        assert_eq!(run("!true").unwrap(), "false");
+        assert_eq!(run("let a = false; !a == true").unwrap(), "true");
+        assert_eq!(run("5! - 20").unwrap(), "100");
+        assert_eq!(run("!(3! == 6)").unwrap(), "false");
+        assert_eq!(run("0!").unwrap(), "1");
+        assert!(matches!(
+            run("(-5)!"),
+            Err(RuntimeError::NegativeFactorial { span }) if span.offset() == 0 && span.len() == 5
+        ));
+        assert!(matches!(
+            run("(-100000000000000000000)!"),
+            Err(RuntimeError::NegativeFactorial { .. })
+        ));
+        assert_eq!(run("21! / 20!").unwrap(), "21");
+        assert!(matches!(run("20001!"), Err(RuntimeError::Overflow { .. })));
+        assert!(matches!(
+            run("100000000!"),
+            Err(RuntimeError::Overflow {
+                operator: Operator::Bang,
+                ..
+            })
+        ));
+        assert!(matches!(
+            run("!1"),
+            Err(RuntimeError::Operand {
+                operator: Operator::Not,
+                operand: "integer",
+                ..
+            })
+        ));
+    }
+    #[test]
+    fn short_circuit() {
+        // The right side would be an error, or a side effect, if it ran.
+        assert_eq!(run("false && undefined").unwrap(), "false");
+        assert_eq!(run("true || 1").unwrap(), "true");
+        assert_eq!(
+            run("let n = 0; let f = || n = n + 1; false && f(); true || f(); n").unwrap(),
+            "0"
+        );
+        assert_eq!(run("let n = 0; true && ((n = 1) == 1); n").unwrap(), "1");
+        assert!(matches!(
+            run("1 || true"),
+            Err(RuntimeError::Logical { found: "integer", span, .. }) if span.offset() == 0
+        ));
+        assert!(matches!(
+            run("true && \"a\""),
+            Err(RuntimeError::Logical { found: "string", span, .. }) if span.offset() == 8
+        ));
+    }
+    #[test]
+    fn if_value() {
+        assert_eq!(run("let a = 3; if a > 2 { 10 } else { 20 }").unwrap(), "10");
+        assert_eq!(run("let a = 1; if a > 2 { 10 } else { 20 }").unwrap(), "20");
+        assert_eq!(run("if false { 10 }").unwrap(), "()");
+        assert_eq!(
+            run("let x = if 1 < 2 { \"yes\" } else { \"no\" }; x").unwrap(),
+            "yes"
+        );
+        assert_eq!(run("1 + if false { 1 } else { 5 }").unwrap(), "6");
+        // At the start of a statement, it doesn't continue on the next line.
+        assert_eq!(run("let a = 1; if a == 1 { a = 2 }\n(a)").unwrap(), "2");
+    }
+    #[test]
+    fn else_if_chains() {
+        let sign = |n| {
+            run(&format!(
+                "let n = {}; if n < 0 {{ -1 }} else if n == 0 {{ 0 }} else {{ 1 }}",
+                n
+            ))
+            .unwrap()
         };
-        unreachable!("HOOOOOOOOL");
+        assert_eq!(sign(-5), "-1");
+        assert_eq!(sign(0), "0");
+        assert_eq!(sign(7), "1");
+        // Only the conditions until the first true one are evaluated.
+        assert_eq!(
+            run("let n = 0; if (n += 1) > 5 { 1 } else if (n += 1) > 1 { 2 } else if (n += 1) > 0 { 3 }; n")
+                .unwrap(),
+            "2"
+        );
+        assert_eq!(run("if false { 1 } else if false { 2 }").unwrap(), "()");
+    }
+    #[test]
+    fn while_loop() {
+        assert_eq!(run("let a = 0; while a < 5 { a = a + 2 } a").unwrap(), "6");
+        assert_eq!(run("while false { 1 }").unwrap(), "()");
+    }
+    #[test]
+    fn for_loops() {
+        assert_eq!(run("let s = 0; for i in 0..5 { s += i } s").unwrap(), "10");
+        assert_eq!(run("let s = 0; for i in 1..=5 { s += i } s").unwrap(), "15");
+        assert_eq!(run("let n = 0; for i in 5..0 { n++ } n").unwrap(), "0");
+        assert_eq!(run("for i in [] { 1 }").unwrap(), "()");
+        assert_eq!(
+            run("let out = []; let a = [\"x\", \"y\"]; for s in a { out = [out, s] } out").unwrap(),
+            "[[[], \"x\"], \"y\"]"
+        );
+        assert_eq!(
+            run("let m = { a: 1, b: 2 }; let s = \"\"; for k in m { s += k } s").unwrap(),
+            "ab"
+        );
+        assert_eq!(
+            run("let m = { a: 1, b: 2 }; for k in m { remove(m, k) } m").unwrap(),
+            "{}"
+        );
+        assert_eq!(
+            run("let s = \"\"; for c in \"héj\" { s = c + s } s").unwrap(),
+            "jéh"
+        );
+        // Every round has its own variable.
+        assert_eq!(
+            run("let fs = [0, 0]; for i in 0..2 { fs[i] = || i } fs[0]() + fs[1]()").unwrap(),
+            "1"
+        );
+        assert!(matches!(run("i"), Err(RuntimeError::Undefined { .. })));
+        assert_eq!(
+            run("let r = 1..3; [r, 0..=2, r == (1..3)]").unwrap(),
+            "[1..3, 0..=2, true]"
+        );
+    }
+    #[test]
+    fn break_and_continue() {
+        assert_eq!(
+            run("let i = 0; while true { if i == 3 { break } else { i++ } } i").unwrap(),
+            "3"
+        );
+        assert_eq!(
+            run("let s = 0; for i in 0..10 { if i % 2 == 0 { continue } else { s += i } } s")
+                .unwrap(),
+            "25"
+        );
+        // Without a label, they go to the innermost loop.
+        assert_eq!(
+            run(
+                "let n = 0; for i in 0..3 { for j in 0..3 { if j == 1 { break } else { n++ } } } n"
+            )
+            .unwrap(),
+            "3"
+        );
+        assert_eq!(
+            run("let n = 0; 'outer: for i in 0..5 { for j in 0..5 { if j == 2 { continue 'outer } else { if i == 3 { break 'outer } else { n++ } } } } n")
+                .unwrap(),
+            "6"
+        );
+        assert_eq!(
+            run("let i = 0; 'a: while (i += 1) < 10 { 'b: while true { break 'a } } i").unwrap(),
+            "1"
+        );
+    }
+    #[test]
+    fn bad_loops() {
+        assert!(matches!(
+            run("for i in 5 { }"),
+            Err(RuntimeError::NotIterable { found: "integer", span }) if span.offset() == 9
+        ));
+        assert!(matches!(
+            run("for i in 0..1.5 { }"),
+            Err(RuntimeError::Operands {
+                operator: Operator::Range,
+                rhs: "float",
+                ..
+            })
+        ));
+        assert!(matches!(
+            run("0..100000000000000000000"),
+            Err(RuntimeError::Overflow { .. })
+        ));
+    }
+    #[test]
+    fn blocks() {
+        assert_eq!(run("let x = { let y = 2; y * 3 }; x").unwrap(), "6");
+        assert_eq!(run("{ 1; 2; }").unwrap(), "()");
+        assert_eq!(
+            run("let a = 0; let b = 0; if true { a = 1; b = 2; } [a, b]").unwrap(),
+            "[1, 2]"
+        );
+        // The variables declared inside are gone after the block.
+        assert!(matches!(
+            run("{ let inner = 1; } inner"),
+            Err(RuntimeError::Undefined { name, .. }) if name == "inner"
+        ));
+        assert_eq!(run("let a = 1; { let a = 2; a = 3; } a").unwrap(), "1");
+        assert_eq!(run("let a = 0; { a = 1; } [1, a]").unwrap(), "[1, 1]");
+        assert_eq!(
+            run("let n = 0; let i = 0; while i < 5 { i++; if i == 2 { continue } n += i; } n")
+                .unwrap(),
+            "13"
+        );
+        assert_eq!(
+            run("fn f(x) { let y = x * 2; if y > 5 { return y; } y + 100 } [f(1), f(3)]").unwrap(),
+            "[102, 6]"
+        );
+    }
+    #[test]
+    fn condition_not_boolean() {
+        assert!(matches!(
+            run("if 1 { 2 }"),
+            Err(RuntimeError::Condition {
+                found: "integer",
+                ..
+            })
+        ));
+    }
+    #[test]
+    fn wrong_operands() {
+        assert!(matches!(
+            run("let a = 1;\n a + \"b\""),
+            Err(RuntimeError::Operands { operator: Operator::Plus, lhs: "integer", rhs: "string", span })
+                if span.offset() == 12 && span.len() == 7
+        ));
+        assert!(matches!(
+            run("-true"),
+            Err(RuntimeError::Operand {
+                operator: Operator::Minus,
+                operand: "boolean",
+                ..
+            })
+        ));
+    }
+    #[test]
+    fn mixed_numbers() {
+        // A float on either side makes the result a float.
+        assert_eq!(run("1 + 2.5").unwrap(), "3.5");
+        assert_eq!(run("2.5 + 1").unwrap(), "3.5");
+        assert_eq!(run("3 / 2.0").unwrap(), "1.5");
+        assert_eq!(run("3 / 2").unwrap(), "1");
+        assert_eq!(run("1 - 0.5 == -0.5 + 1").unwrap(), "true");
+        assert_eq!(run("1 < 1.5").unwrap(), "true");
+        assert_eq!(run("1.5 >= 2").unwrap(), "false");
+        assert_eq!(run("1 == 1.0").unwrap(), "true");
+        assert_eq!(run("let a = 0.5; a++; a").unwrap(), "1.5");
+    }
+    #[test]
+    fn equality() {
+        assert_eq!(run("let a; a == ()").unwrap(), "true");
+        assert_eq!(run("let a = 1; a != ()").unwrap(), "true");
+        // The same list, map or function, not just the same content.
+        assert_eq!(run("let a = [1]; let b = a; a == b").unwrap(), "true");
+        assert_eq!(run("[1] == [1]").unwrap(), "false");
+        assert_eq!(
+            run("let m = {a: 1}; m == m && {a: 1} != m").unwrap(),
+            "true"
+        );
+        assert_eq!(run("fn f() {} let g = f; f == g").unwrap(), "true");
+        assert_eq!(run("(|| 1) == (|| 1)").unwrap(), "false");
+        assert_eq!(run("len == len && len != keys").unwrap(), "true");
+        assert_eq!(run("fn f() {} f == len").unwrap(), "false");
+        assert!(matches!(
+            run("[1] == 1"),
+            Err(RuntimeError::Operands {
+                operator: Operator::EqualEqual,
+                lhs: "list",
+                rhs: "integer",
+                ..
+            })
+        ));
+        assert_eq!(run("() == false").unwrap(), "false");
+    }
+    #[test]
+    fn big_numbers() {
+        assert_eq!(run("3000000000 * 3").unwrap(), "9000000000");
+        assert_eq!(run("20!").unwrap(), "2432902008176640000");
+        assert_eq!(run("0.1 + 0.2").unwrap(), "0.30000000000000004");
+        // They go over to bignums, when they don't fit, and back when they fit again.
+        assert_eq!(
+            run("9223372036854775807 + 1").unwrap(),
+            "9223372036854775808"
+        );
+        assert_eq!(
+            run("let a = -9223372036854775807 - 1; -a - 1").unwrap(),
+            "9223372036854775807"
+        );
+        assert_eq!(run("30! / 29!").unwrap(), "30");
+        assert_eq!(
+            run("let a = 9223372036854775807; a++; a").unwrap(),
+            "9223372036854775808"
+        );
+        assert_eq!(run("25!").unwrap(), "15511210043330985984000000");
+        assert_eq!(
+            run("100000000000000000000 == 10000000000 * 10000000000").unwrap(),
+            "true"
+        );
+        assert_eq!(run("100000000000000000000 > 1.5").unwrap(), "true");
+        assert_eq!(
+            run("100000000000000000000 * 0.5").unwrap(),
+            "50000000000000000000"
+        );
+        assert!(matches!(
+            run("100000000000000000000 / (5 - 5)"),
+            Err(RuntimeError::DivisionByZero { .. })
+        ));
+        assert!(matches!(
+            run("100000000000000000000!"),
+            Err(RuntimeError::Overflow {
+                operator: Operator::Bang,
+                ..
+            })
+        ));
+    }
+    #[test]
+    fn more_arithmetic() {
+        assert_eq!(run("7 % 3").unwrap(), "1");
+        assert_eq!(run("-7 % 3").unwrap(), "-1");
+        assert_eq!(run("7.5 % 2").unwrap(), "1.5");
+        assert_eq!(run("7 ~/ 2").unwrap(), "3");
+        assert_eq!(run("-7.5 ~/ 2").unwrap(), "-3");
+        assert_eq!(run("2 ** 10").unwrap(), "1024");
+        assert_eq!(run("2 ** 3 ** 2").unwrap(), "512");
+        assert_eq!(run("2 ** -1").unwrap(), "0.5");
+        assert_eq!(run("4 ** 0.5").unwrap(), "2");
+        assert_eq!(run("2 ** 64").unwrap(), "18446744073709551616");
+        assert!(matches!(
+            run("1 % 0"),
+            Err(RuntimeError::DivisionByZero { .. })
+        ));
+        assert!(matches!(
+            run("1 ~/ 0.0"),
+            Err(RuntimeError::DivisionByZero { .. })
+        ));
+    }
+    #[test]
+    fn bitwise() {
+        assert_eq!(run("6 & 3").unwrap(), "2");
+        assert_eq!(run("6 | 3").unwrap(), "7");
+        assert_eq!(run("6 ^ 3").unwrap(), "5");
+        assert_eq!(run("~5").unwrap(), "-6");
+        assert_eq!(run("1 << 4").unwrap(), "16");
+        assert_eq!(run("-16 >> 2").unwrap(), "-4");
+        assert_eq!(run("-1 >> 100").unwrap(), "-1");
+        assert_eq!(run("1 << 64").unwrap(), "18446744073709551616");
+        assert_eq!(run("(1 << 64 | 1) & 3").unwrap(), "1");
+        assert!(matches!(
+            run("1 << -1"),
+            Err(RuntimeError::NegativeShift { .. })
+        ));
+        // The result would need more memory than there is.
+        assert!(matches!(
+            run("1 << 9223372036854775807"),
+            Err(RuntimeError::Overflow {
+                operator: Operator::ShiftLeft,
+                ..
+            })
+        ));
+        assert_eq!(run("1 << 1048576 >> 1048576").unwrap(), "1");
+        assert!(matches!(
+            run("10 ** 4000000000"),
+            Err(RuntimeError::Overflow {
+                operator: Operator::Power,
+                ..
+            })
+        ));
+        assert_eq!(run("(-1) ** 4000000001").unwrap(), "-1");
+        assert_eq!(run("(2 ** 1048576) >> 1048576").unwrap(), "1");
+        assert_eq!(run("1 >> 9223372036854775807").unwrap(), "0");
+        assert!(matches!(
+            run("1.5 & 1"),
+            Err(RuntimeError::Operands {
+                operator: Operator::BitAnd,
+                lhs: "float",
+                ..
+            })
+        ));
+        assert!(matches!(
+            run("~true"),
+            Err(RuntimeError::Operand {
+                operator: Operator::BitNot,
+                ..
+            })
+        ));
+    }
+    #[test]
+    fn division_by_zero() {
+        assert!(matches!(
+            run("1 +\n 1 / 0"),
+            Err(RuntimeError::DivisionByZero { span }) if span.offset() == 5 && span.len() == 5
+        ));
+        assert!(matches!(
+            run("1.5 / 0"),
+            Err(RuntimeError::DivisionByZero { .. })
+        ));
+        assert!(matches!(
+            run("let a = 1; a /= 0.0"),
+            Err(RuntimeError::DivisionByZero { .. })
+        ));
+    }
+    #[test]
+    fn computed_strings() {
+        assert_eq!(
+            run("let a = \"ab\"; a = a + \"cd\"; a + a").unwrap(),
+            "abcdabcd"
+        );
+        assert_eq!(run("\"ab\" + \"c\" == \"abc\"").unwrap(), "true");
+    }
+    #[test]
+    fn values_outlive_the_code() {
+        let mut evaluator = Evaluator::new();
+        {
+            let line = String::from("let a = \"kaktusz\";");
+            evaluator.run(Parser::new(&line).parse().unwrap()).unwrap();
+        }
+        let line = String::from("a + \" fa\"");
+        let value = evaluator.run(Parser::new(&line).parse().unwrap()).unwrap();
+        assert_eq!(value.to_string(), "kaktusz fa");
+    }
+    #[test]
+    fn functions() {
+        assert_eq!(
+            run("fn add(a, b) { a + b } add(1, 2) * add(3, 4)").unwrap(),
+            "21"
+        );
+        assert_eq!(
+            run("fn fact(n) { if n <= 1 { 1 } else { n * fact(n - 1) } } fact(10)").unwrap(),
+            "3628800"
+        );
+        assert_eq!(run("fn f() { } f()").unwrap(), "()");
+    }
+    #[test]
+    fn closures() {
+        assert_eq!(run("let add = |a, b| a + b; add(1, 2)").unwrap(), "3");
+        assert_eq!(run("let double = fn(x) { x * 2 }; double(4)").unwrap(), "8");
+        assert_eq!(run("(|| 42)()").unwrap(), "42");
+        assert_eq!(run("|x| x").unwrap(), "<closure>");
+        // Passed to, and returned from other functions.
+        assert_eq!(
+            run("fn twice(f, x) { f(f(x)) } twice(|x| x * 3, 2)").unwrap(),
+            "18"
+        );
+        assert_eq!(
+            run("fn adder(n) { |x| x + n } let add2 = adder(2); add2(3)").unwrap(),
+            "5"
+        );
+    }
+    #[test]
+    fn closures_capture_by_reference() {
+        assert_eq!(run("let n = 1; let get = || n; n = 5; get()").unwrap(), "5");
+        assert_eq!(
+            run("let n = 0; let inc = || n = n + 1; inc(); inc(); n").unwrap(),
+            "2"
+        );
+    }
+    #[test]
+    fn lists() {
+        assert_eq!(
+            run("[1, \"a\", [true, 2.5], []]").unwrap(),
+            "[1, \"a\", [true, 2.5], []]"
+        );
+        assert_eq!(run("let a = [1, 2, 3]; a[0] + a[2]").unwrap(), "4");
+        assert_eq!(
+            run("let a = [[1, 2], [3]]; a[0][1] = 5; a").unwrap(),
+            "[[1, 5], [3]]"
+        );
+        assert_eq!(
+            run("let a = [1, 2]; a[1] += 10; a[0]++; a").unwrap(),
+            "[2, 12]"
+        );
+        // The index is only evaluated once.
+        assert_eq!(
+            run("let i = 0; let a = [1, 2]; a[i++] *= 5; [a, i]").unwrap(),
+            "[[5, 2], 1]"
+        );
+    }
+    #[test]
+    fn lists_containing_themselves() {
+        assert_eq!(run("let a = [1]; a[0] = a; a").unwrap(), "[[...]]");
+        assert_eq!(
+            run("let a = [1, 2]; let b = [a, a]; a[1] = b; b").unwrap(),
+            "[[1, [...]], [1, [...]]]"
+        );
+        // The same list twice, but not inside itself, is printed normally.
+        assert_eq!(run("let a = [1]; [a, [a]]").unwrap(), "[[1], [[1]]]");
+    }
+    #[test]
+    fn lists_are_shared() {
+        assert_eq!(run("let a = [1]; let b = a; b[0] = 2; a").unwrap(), "[2]");
+        assert_eq!(
+            run("fn set(list, i, v) { list[i] = v } let a = [0, 0]; set(a, 1, 9); a").unwrap(),
+            "[0, 9]"
+        );
+        assert_eq!(
+            run("let a = [1]; let f = || a; f()[0] = 3; a").unwrap(),
+            "[3]"
+        );
+    }
+    #[test]
+    fn bad_indexes() {
+        assert!(matches!(
+            run("let a = [1, 2];\n a[2]"),
+            Err(RuntimeError::OutOfBounds { index, length: 2, span })
+                if index == "2" && span.offset() == 17 && span.len() == 4
+        ));
+        assert!(matches!(
+            run("[1][-1]"),
+            Err(RuntimeError::NegativeIndex { index, .. }) if index == "-1"
+        ));
+        assert!(matches!(
+            run("let a = [1]; a[100000000000000000000] = 1"),
+            Err(RuntimeError::OutOfBounds { .. })
+        ));
+        assert!(matches!(
+            run("[1][0.5]"),
+            Err(RuntimeError::IndexType { found: "float", .. })
+        ));
+        assert!(matches!(
+            run("let a = 1; a[0]"),
+            Err(RuntimeError::NotIndexable {
+                found: "integer",
+                ..
+            })
+        ));
+    }
+    #[test]
+    fn maps() {
+        assert_eq!(
+            run("{ \"a\": 1, b: [2], 3: true }").unwrap(),
+            "{\"a\": 1, \"b\": [2], 3: true}"
+        );
+        assert_eq!(run("let m = { a: 1, b: 2 }; m.a + m[\"b\"]").unwrap(), "3");
+        assert_eq!(
+            run("let m = {}; m.x = 1; m[true] = 2; m.x += 10; m").unwrap(),
+            "{\"x\": 11, true: 2}"
+        );
+        assert_eq!(
+            run("let m = { cfg: { port: 80 } }; m.cfg.port++; m.cfg.port").unwrap(),
+            "81"
+        );
+        // The last value wins, but the key stays where it was first.
+        assert_eq!(run("{ a: 1, b: 2, a: 3 }").unwrap(), "{\"a\": 3, \"b\": 2}");
+        assert_eq!(
+            run("let a = {}; let b = a; b.k = 1; a").unwrap(),
+            "{\"k\": 1}"
+        );
+    }
+    #[test]
+    fn maps_containing_themselves() {
+        assert_eq!(
+            run("let m = {}; m.self = m; m").unwrap(),
+            "{\"self\": {...}}"
+        );
+        assert_eq!(
+            run("let m = { a: [0] }; m.a[0] = m; [m, m.a]").unwrap(),
+            "[{\"a\": [{...}]}, [{\"a\": [...]}]]"
+        );
+    }
+    #[test]
+    fn map_builtins() {
+        assert_eq!(
+            run("let m = { a: 1, b: 2, c: 3 }; let b = remove(m, \"b\"); [b, m]").unwrap(),
+            "[2, {\"a\": 1, \"c\": 3}]"
+        );
+        assert_eq!(
+            run("let m = { z: 1, a: 2 }; m.m = 3; [keys(m), values(m)]").unwrap(),
+            "[[\"z\", \"a\", \"m\"], [1, 2, 3]]"
+        );
+        assert_eq!(
+            run("[len({ a: 1 }), len([1, 2]), len(\"héj\")]").unwrap(),
+            "[1, 2, 3]"
+        );
+        assert_eq!(run("len").unwrap(), "<builtin len>");
+        assert!(matches!(
+            run("keys([1])"),
+            Err(RuntimeError::ArgumentType {
+                name: "keys",
+                found: "list",
+                ..
+            })
+        ));
+        assert!(matches!(
+            run("remove({}, 1, 2)"),
+            Err(RuntimeError::Arity {
+                expected: 2,
+                found: 3,
+                ..
+            })
+        ));
+    }
+    #[test]
+    fn bad_keys() {
+        assert!(matches!(
+            run("let m = { a: 1 };\n m.b"),
+            Err(RuntimeError::MissingKey { key, span }) if key == "\"b\"" && span.offset() == 19
+        ));
+        assert!(matches!(
+            run("remove({}, 1)"),
+            Err(RuntimeError::MissingKey { key, .. }) if key == "1"
+        ));
+        assert!(matches!(
+            run("let m = {}; m[1.5] = 1"),
+            Err(RuntimeError::KeyType { found: "float", .. })
+        ));
+        assert!(matches!(
+            run("{ a: 1 }[[1]]"),
+            Err(RuntimeError::KeyType { found: "list", .. })
+        ));
+        assert!(matches!(
+            run("let m = {}; m.a += 1"),
+            Err(RuntimeError::MissingKey { .. })
+        ));
+    }
+    #[test]
+    fn return_unwinds() {
+        assert_eq!(
+            run("fn f(a) { while true { if a > 3 { return a } else { a = a + 1 } } } f(0)")
+                .unwrap(),
+            "4"
+        );
+        // Outside of a function, it stops the program.
+        assert_eq!(
+            run("let a = 0; while true { if a == 4 { return \"done\" } else { a = a + 1 } }; a")
+                .unwrap(),
+            "done"
+        );
+    }
+    #[test]
+    fn function_scope() {
+        // The function sees the variables around its declaration, but not the caller's.
+        assert_eq!(
+            run("let a = 1; fn f() { a } fn g(a) { f() } g(2)").unwrap(),
+            "1"
+        );
+        assert!(matches!(
+            run("fn f() { b } fn g(b) { f() } g(2)"),
+            Err(RuntimeError::Undefined { name, .. }) if name == "b"
+        ));
+        assert_eq!(run("let a = 1; fn f(a) { a = 5 } f(2); a").unwrap(), "1");
+    }
+    #[test]
+    fn recursion_limit() {
+        // The tests run on 2 MiB stacks, the limit is meant for the 8 MiB of a main thread.
+        std::thread::Builder::new()
+            .stack_size(8 * 1024 * 1024)
+            .spawn(|| {
+                let deep = |depth| {
+                    run(&format!(
+                        "fn f(n) {{ if n == 0 {{ 0 }} else {{ f(n - 1) }} }} f({})",
+                        depth
+                    ))
+                };
+                assert_eq!(deep(MAX_DEPTH - 1).unwrap(), "0");
+                assert!(matches!(
+                    deep(100_000),
+                    Err(RuntimeError::RecursionLimit { limit: MAX_DEPTH, span }) if span.offset() == 33
+                ));
+                // The depth goes back down after the error, so the evaluator can be used again.
+                let mut evaluator = Evaluator::new();
+                evaluator
+                    .run(Parser::new("fn g() { g() }").parse().unwrap())
+                    .unwrap();
+                for _ in 0..2 {
+                    assert!(matches!(
+                        evaluator.run(Parser::new("g()").parse().unwrap()),
+                        Err(RuntimeError::RecursionLimit { .. })
+                    ));
+                }
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+    #[test]
+    fn bad_calls() {
+        assert!(matches!(
+            run("fn f(a, b) { a } f(1)"),
+            Err(RuntimeError::Arity {
+                expected: 2,
+                found: 1,
+                ..
+            })
+        ));
+        assert!(matches!(
+            run("let a = 1; a(2)"),
+            Err(RuntimeError::NotCallable { found: "integer", span }) if span.offset() == 11
+        ));
+    }
+    #[test]
+    fn shadowing() {
+        let mut evaluator = Evaluator::new();
+        evaluator
+            .run(Parser::new("let a = 1; let b = 2;").parse().unwrap())
+            .unwrap();
+
+        // The inner declaration is gone with its scope, but the assignment reaches the outer one.
+        evaluator.evaluate(&tree("{ let a = 10 }")).unwrap();
+        evaluator.evaluate(&tree("{ b = a + 5 }")).unwrap();
+
+        assert_eq!(evaluator.evaluate(&tree("a")).unwrap().to_string(), "1");
+        assert_eq!(evaluator.evaluate(&tree("b")).unwrap().to_string(), "6");
     }
 }
@@ -1,193 +1,327 @@
-use super::{Atomic, EvalErr};
+use std::{cmp::Ordering, rc::Rc};
 
-pub fn add_together<'a>(lhs: Atomic, rhs: Atomic) -> Result<Atomic<'a>, EvalErr> {
-    match lhs {
-        Atomic::Float(float1) => match rhs {
-            Atomic::Integer(integer) => Ok(Atomic::Float(float1 + integer as f32)),
-            Atomic::Float(float2) => Ok(Atomic::Float(float1 + float2)),
-            _ => Err(EvalErr::CantAdd)
-        },
-        Atomic::Integer(integer1) => match rhs {
-            Atomic::Integer(integer2) => Ok(Atomic::Integer(integer1 + integer2)),
-            Atomic::Float(float) => Ok(Atomic::Integer(integer1 + float as i32)),
-            _ => Err(EvalErr::CantAdd)
-        },
-        _ => Err(EvalErr::WrongType)
-    }
-}
-pub fn sub_together<'a>(lhs: Atomic, rhs: Atomic) -> Result<Atomic<'a>, EvalErr> {
-    match lhs {
-        Atomic::Float(float1) => match rhs {
-            Atomic::Integer(integer) => Ok(Atomic::Float(float1 - integer as f32)),
-            Atomic::Float(float2) => Ok(Atomic::Float(float1 - float2)),
-            _ => Err(EvalErr::CantAdd)
-        },
-        Atomic::Integer(integer1) => match rhs {
-            Atomic::Integer(integer2) => Ok(Atomic::Integer(integer1 - integer2)),
-            Atomic::Float(float) => Ok(Atomic::Integer(integer1 - float as i32)),
-            _ => Err(EvalErr::CantAdd)
-        },
-        _ => Err(EvalErr::WrongType)
-    }
-}
-pub fn mul_together<'a>(lhs: Atomic, rhs: Atomic) -> Result<Atomic<'a>, EvalErr> {
-    match lhs {
-        Atomic::Float(float1) => match rhs {
-            Atomic::Integer(integer) => Ok(Atomic::Float(float1 * integer as f32)),
-            Atomic::Float(float2) => Ok(Atomic::Float(float1 * float2)),
-            _ => Err(EvalErr::CantAdd)
-        },
-        Atomic::Integer(integer1) => match rhs {
-            Atomic::Integer(integer2) => Ok(Atomic::Integer(integer1 * integer2)),
-            Atomic::Float(float) => Ok(Atomic::Integer(integer1 * float as i32)),
-            _ => Err(EvalErr::CantAdd)
-        },
-        _ => Err(EvalErr::WrongType)
-    }
-}
-pub fn div_together<'a>(lhs: Atomic, rhs: Atomic) -> Result<Atomic<'a>, EvalErr> {
-    match lhs {
-        Atomic::Float(float1) => match rhs {
-            Atomic::Integer(integer) => Ok(Atomic::Float(float1 / integer as f32)),
-            Atomic::Float(float2) => Ok(Atomic::Float(float1 / float2)),
-            _ => Err(EvalErr::CantAdd)
-        },
-        Atomic::Integer(integer1) => match rhs {
-            Atomic::Integer(integer2) => Ok(Atomic::Integer(integer1 / integer2)),
-            Atomic::Float(float) => Ok(Atomic::Integer(integer1 / float as i32)),
-            _ => Err(EvalErr::CantAdd)
-        },
-        _ => Err(EvalErr::WrongType)
-    }
-}
-pub fn factor<'a>(lhs: Atomic) -> Result<Atomic<'a>, EvalErr> {
-    match lhs {
-        Atomic::Integer(integer) => Ok(Atomic::Integer((1..=integer).product())),
-        _ => Err(EvalErr::WrongType)
+use num_bigint::BigInt;
+use num_traits::{FromPrimitive, Signed, ToPrimitive, Zero};
+
+use super::{EvalErr, Range, Value};
+
+/// Two numbers, that are ready to be used together.
+/// If one of them is a float, the other one is turned into a float too,
+/// so the order of the operands doesn't change the type of the result.
+/// Otherwise if one of them is a bignum, both are.
+enum Numbers {
+    Integers(i64, i64),
+    BigInts(BigInt, BigInt),
+    Floats(f64, f64),
+}
+/// The numeric coercion, every operation on numbers goes through here.
+fn numbers(lhs: &Value, rhs: &Value) -> Option<Numbers> {
+    match (lhs, rhs) {
+        (Value::Integer(lhs), Value::Integer(rhs)) => Some(Numbers::Integers(*lhs, *rhs)),
+        (Value::Float(lhs), _) => Some(Numbers::Floats(*lhs, float(rhs)?)),
+        (_, Value::Float(rhs)) => Some(Numbers::Floats(float(lhs)?, *rhs)),
+        _ => Some(Numbers::BigInts(big(lhs)?, big(rhs)?)),
     }
 }
-pub fn and_together<'a>(lhs: Atomic, rhs: Atomic) -> Result<Atomic<'a>, EvalErr> {
-    match lhs {
-        Atomic::Boolean(boolean1) => match rhs {
-            Atomic::Boolean(boolean2) => return Ok(Atomic::Boolean(boolean1 && boolean2)),
-            _ => Err(EvalErr::WrongType)
-        },
-        _ => Err(EvalErr::WrongType)
+fn float(value: &Value) -> Option<f64> {
+    match value {
+        Value::Integer(num) => Some(*num as f64),
+        Value::BigInt(num) => num.to_f64(),
+        Value::Float(num) => Some(*num),
+        _ => None,
     }
 }
-pub fn or_together<'a>(lhs: Atomic, rhs: Atomic) -> Result<Atomic<'a>, EvalErr> {
-    match lhs {
-        Atomic::Boolean(boolean1) => match rhs {
-            Atomic::Boolean(boolean2) => return Ok(Atomic::Boolean(boolean1 || boolean2)),
-            _ => Err(EvalErr::WrongType)
-        },
-        _ => Err(EvalErr::WrongType)
+fn big(value: &Value) -> Option<BigInt> {
+    match value {
+        Value::Integer(num) => Some(BigInt::from(*num)),
+        Value::BigInt(num) => Some((**num).clone()),
+        _ => None,
+    }
+}
+/// Does the same operation on every kind of numbers.
+/// The `i64` one is checked, if it returns `None`, it is done again with bignums.
+fn arithmetic(
+    lhs: &Value,
+    rhs: &Value,
+    integers: fn(i64, i64) -> Option<i64>,
+    bigints: fn(BigInt, BigInt) -> BigInt,
+    floats: fn(f64, f64) -> f64,
+) -> Result<Value, EvalErr> {
+    match numbers(lhs, rhs) {
+        Some(Numbers::Integers(lhs, rhs)) => Ok(match integers(lhs, rhs) {
+            Some(num) => Value::Integer(num),
+            None => bigints(lhs.into(), rhs.into()).into(),
+        }),
+        Some(Numbers::BigInts(lhs, rhs)) => Ok(bigints(lhs, rhs).into()),
+        Some(Numbers::Floats(lhs, rhs)) => Ok(Value::Float(floats(lhs, rhs))),
+        None => Err(EvalErr::CantAdd),
+    }
+}
+/// Bitwise operators only work on integers.
+fn bitwise(
+    lhs: &Value,
+    rhs: &Value,
+    integers: fn(i64, i64) -> i64,
+    bigints: fn(BigInt, BigInt) -> BigInt,
+) -> Result<Value, EvalErr> {
+    match numbers(lhs, rhs) {
+        Some(Numbers::Integers(lhs, rhs)) => Ok(Value::Integer(integers(lhs, rhs))),
+        Some(Numbers::BigInts(lhs, rhs)) => Ok(bigints(lhs, rhs).into()),
+        _ => Err(EvalErr::WrongType),
+    }
+}
+/// The most bits the result of `<<` and `**` can have, a bigger one would take all the memory.
+const MAX_BITS: u64 = 1 << 20;
+/// Shifts are done on bignums, so `<<` can't overflow, the amount can be at most `limit`.
+fn shift(
+    lhs: &Value,
+    rhs: &Value,
+    limit: u64,
+    shift: fn(BigInt, usize) -> BigInt,
+) -> Result<Value, EvalErr> {
+    let (Some(lhs), Some(rhs)) = (big(lhs), big(rhs)) else {
+        return Err(EvalErr::WrongType);
+    };
+    if rhs.is_negative() {
+        return Err(EvalErr::NegativeShift);
+    }
+    let amount = rhs
+        .to_u64()
+        .filter(|amount| *amount <= limit)
+        .and_then(|amount| usize::try_from(amount).ok())
+        .ok_or(EvalErr::Overflow)?;
+    Ok(shift(lhs, amount).into())
+}
+/// The right side of `/`, `~/` and `%` can't be zero, not even for floats.
+fn divisor(lhs: &Value, rhs: &Value) -> Result<(), EvalErr> {
+    match numbers(lhs, rhs) {
+        Some(Numbers::Integers(_, 0)) | Some(Numbers::Floats(_, 0.0)) => {
+            Err(EvalErr::DivisionByZero)
+        }
+        Some(Numbers::BigInts(_, rhs)) if rhs.is_zero() => Err(EvalErr::DivisionByZero),
+        _ => Ok(()),
+    }
+}
+/// Only numbers can be ordered. `None` if one of them is NaN, then every comparison is false.
+fn compare(lhs: &Value, rhs: &Value) -> Result<Option<Ordering>, EvalErr> {
+    match numbers(lhs, rhs) {
+        Some(Numbers::Integers(lhs, rhs)) => Ok(Some(lhs.cmp(&rhs))),
+        Some(Numbers::BigInts(lhs, rhs)) => Ok(Some(lhs.cmp(&rhs))),
+        Some(Numbers::Floats(lhs, rhs)) => Ok(lhs.partial_cmp(&rhs)),
+        None => Err(EvalErr::CantCompare),
+    }
+}
+/// Numbers are equal after the promotion, other values only to the same type,
+/// except nil, that anything can be checked for.
+/// Lists, maps and functions are only equal to themselves, not to a copy with the same content,
+/// like `let b = a; b == a`, because a change to one of them is seen through the other one too.
+fn equal(lhs: &Value, rhs: &Value) -> Result<bool, EvalErr> {
+    match (lhs, rhs) {
+        (Value::Nil, Value::Nil) => Ok(true),
+        (Value::Nil, _) | (_, Value::Nil) => Ok(false),
+        (Value::Boolean(lhs), Value::Boolean(rhs)) => Ok(lhs == rhs),
+        (Value::String(lhs), Value::String(rhs)) => Ok(lhs == rhs),
+        (Value::Range(lhs), Value::Range(rhs)) => Ok(lhs == rhs),
+        (Value::List(lhs), Value::List(rhs)) => Ok(Rc::ptr_eq(lhs, rhs)),
+        (Value::Map(lhs), Value::Map(rhs)) => Ok(Rc::ptr_eq(lhs, rhs)),
+        (Value::Function(lhs), Value::Function(rhs)) => Ok(Rc::ptr_eq(lhs, rhs)),
+        (Value::Builtin(lhs), Value::Builtin(rhs)) => Ok(std::ptr::eq(*lhs, *rhs)),
+        (Value::Function(_), Value::Builtin(_)) | (Value::Builtin(_), Value::Function(_)) => {
+            Ok(false)
+        }
+        _ => compare(lhs, rhs).map(|ordering| ordering == Some(Ordering::Equal)),
     }
 }
 
-pub fn less_than_together<'a>(lhs: Atomic, rhs: Atomic) -> Result<Atomic<'a>, EvalErr> {
-    match lhs {
-        Atomic::Float(float1) => match rhs {
-            Atomic::Integer(integer) => Ok(Atomic::Boolean(float1 < integer as f32)),
-            Atomic::Float(float2) => Ok(Atomic::Boolean(float1 < float2)),
-            _ => Err(EvalErr::CantCompare)
-        },
-        Atomic::Integer(integer1) => match rhs {
-            Atomic::Integer(integer2) => Ok(Atomic::Boolean(integer1 < integer2)),
-            Atomic::Float(float) => Ok(Atomic::Boolean(integer1 < float as i32)),
-            _ => Err(EvalErr::CantCompare)
-        },
-        _ => Err(EvalErr::WrongType)
-    }
-}
-pub fn lessequal_than_together<'a>(lhs: Atomic, rhs: Atomic) -> Result<Atomic<'a>, EvalErr> {
-    match lhs {
-        Atomic::Float(float1) => match rhs {
-            Atomic::Integer(integer) => Ok(Atomic::Boolean(float1 <= integer as f32)),
-            Atomic::Float(float2) => Ok(Atomic::Boolean(float1 <= float2)),
-            _ => Err(EvalErr::CantCompare)
-        },
-        Atomic::Integer(integer1) => match rhs {
-            Atomic::Integer(integer2) => Ok(Atomic::Boolean(integer1 <= integer2)),
-            Atomic::Float(float) => Ok(Atomic::Boolean(integer1 <= float as i32)),
-            _ => Err(EvalErr::CantCompare)
-        },
-        _ => Err(EvalErr::WrongType)
-    }
-}
-pub fn greater_than_together<'a>(lhs: Atomic, rhs: Atomic) -> Result<Atomic<'a>, EvalErr> {
-    match lhs {
-        Atomic::Float(float1) => match rhs {
-            Atomic::Integer(integer) => Ok(Atomic::Boolean(float1 > integer as f32)),
-            Atomic::Float(float2) => Ok(Atomic::Boolean(float1 > float2)),
-            _ => Err(EvalErr::CantCompare)
-        },
-        Atomic::Integer(integer1) => match rhs {
-            Atomic::Integer(integer2) => Ok(Atomic::Boolean(integer1 > integer2)),
-            Atomic::Float(float) => Ok(Atomic::Boolean(integer1 > float as i32)),
-            _ => Err(EvalErr::CantCompare)
-        },
-        _ => Err(EvalErr::WrongType)
-    }
-}
-pub fn greaterequal_than_together<'a>(lhs: Atomic, rhs: Atomic) -> Result<Atomic<'a>, EvalErr> {
-    match lhs {
-        Atomic::Float(float1) => match rhs {
-            Atomic::Integer(integer) => Ok(Atomic::Boolean(float1 >= integer as f32)),
-            Atomic::Float(float2) => Ok(Atomic::Boolean(float1 >= float2)),
-            _ => Err(EvalErr::CantCompare)
-        },
-        Atomic::Integer(integer1) => match rhs {
-            Atomic::Integer(integer2) => Ok(Atomic::Boolean(integer1 >= integer2)),
-            Atomic::Float(float) => Ok(Atomic::Boolean(integer1 >= float as i32)),
-            _ => Err(EvalErr::CantCompare)
-        },
-        _ => Err(EvalErr::WrongType)
-    }
-}
-pub fn equal_with_together<'a>(lhs: Atomic, rhs: Atomic) -> Result<Atomic<'a>, EvalErr> {
-    match lhs {
-        Atomic::Float(float1) => match rhs {
-            Atomic::Integer(integer) => Ok(Atomic::Boolean(float1 == integer as f32)),
-            Atomic::Float(float2) => Ok(Atomic::Boolean(float1 == float2)),
-            _ => Err(EvalErr::CantCompare)
-        },
-        Atomic::Integer(integer1) => match rhs {
-            Atomic::Integer(integer2) => Ok(Atomic::Boolean(integer1 == integer2)),
-            Atomic::Float(float) => Ok(Atomic::Boolean(integer1 == float as i32)),
-            _ => Err(EvalErr::CantCompare)
-        },
-        Atomic::Boolean(boolean1) => match rhs {
-            Atomic::Boolean(boolean2) => Ok(Atomic::Boolean(boolean1 == boolean2)),
-            _ => Err(EvalErr::CantCompare)
-        },
-        Atomic::String(string1) => match rhs {
-            Atomic::String(string2) => Ok(Atomic::Boolean(string1 == string2)),
-            _ => Err(EvalErr::CantCompare)
+pub fn add_together(lhs: Value, rhs: Value) -> Result<Value, EvalErr> {
+    match (&lhs, &rhs) {
+        (Value::String(lhs), Value::String(rhs)) => {
+            Ok(Value::String(format!("{}{}", lhs, rhs).into()))
         }
-        _ => Err(EvalErr::WrongType)
+        _ => arithmetic(
+            &lhs,
+            &rhs,
+            i64::checked_add,
+            |lhs, rhs| lhs + rhs,
+            |lhs, rhs| lhs + rhs,
+        ),
     }
 }
-pub fn notequal_with_together<'a>(lhs: Atomic, rhs: Atomic) -> Result<Atomic<'a>, EvalErr> {
-    match lhs {
-        Atomic::Float(float1) => match rhs {
-            Atomic::Integer(integer) => Ok(Atomic::Boolean(float1 != integer as f32)),
-            Atomic::Float(float2) => Ok(Atomic::Boolean(float1 != float2)),
-            _ => Err(EvalErr::CantCompare)
-        },
-        Atomic::Integer(integer1) => match rhs {
-            Atomic::Integer(integer2) => Ok(Atomic::Boolean(integer1 != integer2)),
-            Atomic::Float(float) => Ok(Atomic::Boolean(integer1 != float as i32)),
-            _ => Err(EvalErr::CantCompare)
-        },
-        Atomic::Boolean(boolean1) => match rhs {
-            Atomic::Boolean(boolean2) => Ok(Atomic::Boolean(boolean1 != boolean2)),
-            _ => Err(EvalErr::CantCompare)
-        },
-        Atomic::String(string1) => match rhs {
-            Atomic::String(string2) => Ok(Atomic::Boolean(string1 != string2)),
-            _ => Err(EvalErr::CantCompare)
+pub fn sub_together(lhs: Value, rhs: Value) -> Result<Value, EvalErr> {
+    arithmetic(
+        &lhs,
+        &rhs,
+        i64::checked_sub,
+        |lhs, rhs| lhs - rhs,
+        |lhs, rhs| lhs - rhs,
+    )
+}
+pub fn mul_together(lhs: Value, rhs: Value) -> Result<Value, EvalErr> {
+    arithmetic(
+        &lhs,
+        &rhs,
+        i64::checked_mul,
+        |lhs, rhs| lhs * rhs,
+        |lhs, rhs| lhs * rhs,
+    )
+}
+/// Dividing by zero is an error for floats too, instead of infinity.
+pub fn div_together(lhs: Value, rhs: Value) -> Result<Value, EvalErr> {
+    divisor(&lhs, &rhs)?;
+    arithmetic(
+        &lhs,
+        &rhs,
+        i64::checked_div,
+        |lhs, rhs| lhs / rhs,
+        |lhs, rhs| lhs / rhs,
+    )
+}
+/// Rounds towards zero, and gives an integer even for floats.
+pub fn int_div_together(lhs: Value, rhs: Value) -> Result<Value, EvalErr> {
+    match div_together(lhs, rhs)? {
+        Value::Float(float) => BigInt::from_f64(float.trunc())
+            .map(Value::from)
+            .ok_or(EvalErr::Overflow),
+        integer => Ok(integer),
+    }
+}
+pub fn rem_together(lhs: Value, rhs: Value) -> Result<Value, EvalErr> {
+    divisor(&lhs, &rhs)?;
+    arithmetic(
+        &lhs,
+        &rhs,
+        i64::checked_rem,
+        |lhs, rhs| lhs % rhs,
+        |lhs, rhs| lhs % rhs,
+    )
+}
+/// Integers stay integers, unless the exponent is negative, then it is a float.
+pub fn pow_together(lhs: Value, rhs: Value) -> Result<Value, EvalErr> {
+    match numbers(&lhs, &rhs) {
+        Some(Numbers::Integers(base, exponent)) if exponent >= 0 => {
+            let exponent = u32::try_from(exponent).map_err(|_| EvalErr::Overflow)?;
+            match base.checked_pow(exponent) {
+                Some(power) => Ok(Value::Integer(power)),
+                None => big_pow(BigInt::from(base), exponent),
+            }
+        }
+        Some(Numbers::BigInts(base, exponent)) if !exponent.is_negative() => {
+            let exponent = exponent.to_u32().ok_or(EvalErr::Overflow)?;
+            big_pow(base, exponent)
         }
-        _ => Err(EvalErr::WrongType)
+        Some(Numbers::Integers(..) | Numbers::BigInts(..)) => {
+            let (Some(base), Some(exponent)) = (float(&lhs), float(&rhs)) else {
+                unreachable!("Integers can always be floats.")
+            };
+            Ok(Value::Float(base.powf(exponent)))
+        }
+        Some(Numbers::Floats(base, exponent)) => Ok(Value::Float(base.powf(exponent))),
+        None => Err(EvalErr::CantAdd),
+    }
+}
+/// The result has at least `(bits of the base - 1) * exponent` bits,
+/// that can't be more than `MAX_BITS`. So 0, 1 and -1 can have any exponent.
+fn big_pow(base: BigInt, exponent: u32) -> Result<Value, EvalErr> {
+    if base
+        .bits()
+        .saturating_sub(1)
+        .saturating_mul(exponent.into())
+        > MAX_BITS
+    {
+        return Err(EvalErr::Overflow);
+    }
+    Ok(base.pow(exponent).into())
+}
+pub fn bitand_together(lhs: Value, rhs: Value) -> Result<Value, EvalErr> {
+    bitwise(&lhs, &rhs, |lhs, rhs| lhs & rhs, |lhs, rhs| lhs & rhs)
+}
+pub fn bitor_together(lhs: Value, rhs: Value) -> Result<Value, EvalErr> {
+    bitwise(&lhs, &rhs, |lhs, rhs| lhs | rhs, |lhs, rhs| lhs | rhs)
+}
+pub fn bitxor_together(lhs: Value, rhs: Value) -> Result<Value, EvalErr> {
+    bitwise(&lhs, &rhs, |lhs, rhs| lhs ^ rhs, |lhs, rhs| lhs ^ rhs)
+}
+pub fn shl_together(lhs: Value, rhs: Value) -> Result<Value, EvalErr> {
+    shift(&lhs, &rhs, MAX_BITS, |lhs, rhs| lhs << rhs)
+}
+/// Keeps the sign, so it rounds down for negative numbers too.
+pub fn shr_together(lhs: Value, rhs: Value) -> Result<Value, EvalErr> {
+    shift(&lhs, &rhs, u64::MAX, |lhs, rhs| lhs >> rhs)
+}
+/// Only `i64` bounds, a range of bignums would never finish anyway.
+pub fn range_together(lhs: Value, rhs: Value, inclusive: bool) -> Result<Value, EvalErr> {
+    match (lhs, rhs) {
+        (Value::Integer(start), Value::Integer(end)) => Ok(Value::Range(Range {
+            start,
+            end,
+            inclusive,
+        })),
+        (Value::BigInt(_), Value::Integer(_) | Value::BigInt(_))
+        | (Value::Integer(_), Value::BigInt(_)) => Err(EvalErr::Overflow),
+        _ => Err(EvalErr::WrongType),
+    }
+}
+pub fn bit_not(operand: Value) -> Result<Value, EvalErr> {
+    match operand {
+        Value::Integer(integer) => Ok(Value::Integer(!integer)),
+        Value::BigInt(integer) => Ok((!(*integer).clone()).into()),
+        _ => Err(EvalErr::WrongType),
+    }
+}
+pub fn negate(operand: Value) -> Result<Value, EvalErr> {
+    match operand {
+        Value::Integer(integer) => Ok(match integer.checked_neg() {
+            Some(integer) => Value::Integer(integer),
+            None => (-BigInt::from(integer)).into(),
+        }),
+        Value::BigInt(integer) => Ok((-(*integer).clone()).into()),
+        Value::Float(float) => Ok(Value::Float(-float)),
+        _ => Err(EvalErr::WrongType),
+    }
+}
+/// The biggest number `!` works on, its factorial has about 250 thousand bits,
+/// above it the multiplication would take too long.
+const MAX_FACTORIAL: i64 = 20_000;
+/// Switches to bignums, if it doesn't fit. It only goes up to `MAX_FACTORIAL`,
+/// negative numbers don't have one.
+pub fn factor(lhs: Value) -> Result<Value, EvalErr> {
+    match lhs {
+        Value::Integer(integer) if integer < 0 => Err(EvalErr::NegativeFactorial),
+        Value::BigInt(integer) if integer.is_negative() => Err(EvalErr::NegativeFactorial),
+        Value::Integer(integer) if integer > MAX_FACTORIAL => Err(EvalErr::Overflow),
+        Value::Integer(integer) => Ok(match (1..=integer).try_fold(1, i64::checked_mul) {
+            Some(product) => Value::Integer(product),
+            None => (1..=integer).map(BigInt::from).product::<BigInt>().into(),
+        }),
+        Value::BigInt(_) => Err(EvalErr::Overflow),
+        _ => Err(EvalErr::WrongType),
     }
-}
\ No newline at end of file
+}
+pub fn less_than_together(lhs: Value, rhs: Value) -> Result<Value, EvalErr> {
+    let ordering = compare(&lhs, &rhs)?;
+    Ok(Value::Boolean(ordering == Some(Ordering::Less)))
+}
+pub fn lessequal_than_together(lhs: Value, rhs: Value) -> Result<Value, EvalErr> {
+    let ordering = compare(&lhs, &rhs)?;
+    Ok(Value::Boolean(matches!(
+        ordering,
+        Some(Ordering::Less | Ordering::Equal)
+    )))
+}
+pub fn greater_than_together(lhs: Value, rhs: Value) -> Result<Value, EvalErr> {
+    let ordering = compare(&lhs, &rhs)?;
+    Ok(Value::Boolean(ordering == Some(Ordering::Greater)))
+}
+pub fn greaterequal_than_together(lhs: Value, rhs: Value) -> Result<Value, EvalErr> {
+    let ordering = compare(&lhs, &rhs)?;
+    Ok(Value::Boolean(matches!(
+        ordering,
+        Some(Ordering::Greater | Ordering::Equal)
+    )))
+}
+pub fn equal_with_together(lhs: Value, rhs: Value) -> Result<Value, EvalErr> {
+    equal(&lhs, &rhs).map(Value::Boolean)
+}
+pub fn notequal_with_together(lhs: Value, rhs: Value) -> Result<Value, EvalErr> {
+    equal(&lhs, &rhs).map(|equal| Value::Boolean(!equal))
+}
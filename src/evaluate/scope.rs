@@ -0,0 +1,50 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use super::Value;
+
+/// One level of variables. Every block gets its own, and if a name is not found in it,
+/// we go up the chain to the parent, so inner scopes can shadow the outer ones.
+pub struct Scope {
+    variables: HashMap<String, Value>,
+    parent: Option<Rc<RefCell<Scope>>>,
+}
+impl Scope {
+    pub fn new() -> Rc<RefCell<Scope>> {
+        Rc::new(RefCell::new(Scope {
+            variables: HashMap::new(),
+            parent: None,
+        }))
+    }
+    pub fn child(parent: Rc<RefCell<Scope>>) -> Rc<RefCell<Scope>> {
+        Rc::new(RefCell::new(Scope {
+            variables: HashMap::new(),
+            parent: Some(parent),
+        }))
+    }
+    /// `let name = value`, always creates the variable in this scope,
+    /// even if the same name already exists here or in a parent.
+    pub fn declare(&mut self, name: &str, value: Value) {
+        self.variables.insert(name.to_string(), value);
+    }
+    /// `name = value`, changes the closest variable with this name.
+    /// Returns false, if there is no such variable.
+    pub fn assign(&mut self, name: &str, value: Value) -> bool {
+        if let Some(variable) = self.variables.get_mut(name) {
+            *variable = value;
+            return true;
+        }
+        match &self.parent {
+            Some(parent) => parent.borrow_mut().assign(name, value),
+            None => false,
+        }
+    }
+    pub fn get(&self, name: &str) -> Option<Value> {
+        if let Some(variable) = self.variables.get(name) {
+            return Some(variable.clone());
+        }
+        match &self.parent {
+            Some(parent) => parent.borrow().get(name),
+            None => None,
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use std::{cell::RefCell, fmt::Display, rc::Rc};
+
+use indexmap::IndexMap;
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+
+use super::{builtins::Builtin, scope::Scope, RuntimeError};
+use crate::parse::{Atomic, Span, TokenTree};
+
+/// What the evaluator works with. Unlike `Atomic`, it doesn't borrow from the code,
+/// so it can outlive the line it was made on, and strings can be computed at runtime.
+/// Anything bigger than a few bytes lives behind an `Rc`, so cloning a value stays cheap.
+#[derive(Clone, Debug)]
+pub enum Value {
+    Integer(i64),
+    /// Only for integers, that don't fit in an `i64`, the smaller ones are always `Integer`.
+    BigInt(Rc<BigInt>),
+    Float(f64),
+    String(Rc<str>),
+    Boolean(bool),
+    Nil,
+    Function(Rc<Function>),
+    Builtin(&'static Builtin),
+    List(List),
+    Map(Map),
+    Range(Range),
+}
+/// Shared, so every copy of it sees the changes: `let b = a; b[0] = 1` changes `a` too.
+pub type List = Rc<RefCell<Vec<Value>>>;
+/// Shared like the lists. It remembers the order the keys were added in.
+pub type Map = Rc<RefCell<IndexMap<Key, Value>>>;
+/// The values, that can be hashed, so they can be the keys of a map.
+/// Floats can't, because `NaN` is not equal to itself.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Key {
+    Integer(i64),
+    BigInt(Rc<BigInt>),
+    String(Rc<str>),
+    Boolean(bool),
+}
+/// `start..end`, or `start..=end` if `inclusive`. It is not a list,
+/// so `0..1000000` doesn't take any memory, the numbers are made while it is walked.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Range {
+    pub start: i64,
+    pub end: i64,
+    pub inclusive: bool,
+}
+/// A declared function or a closure, it keeps the scope it was made in,
+/// so it can use the variables around it, even after that scope has ended.
+/// The scope is shared, not copied, so it sees the later changes too.
+pub struct Function {
+    /// Closures don't have one.
+    pub name: Option<Rc<str>>,
+    pub parameters: Vec<Rc<str>>,
+    pub body: Rc<TokenTree>,
+    pub scope: Rc<RefCell<Scope>>,
+}
+impl Value {
+    /// Name of the type for the error messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Integer(_) | Value::BigInt(_) => "integer",
+            Value::Float(_) => "float",
+            Value::String(_) => "string",
+            Value::Boolean(_) => "boolean",
+            Value::Nil => "nil",
+            Value::Function(_) | Value::Builtin(_) => "function",
+            Value::List(_) => "list",
+            Value::Map(_) => "map",
+            Value::Range(_) => "range",
+        }
+    }
+    /// Inside of lists and maps the strings are quoted, so `["1"]` and `[1]` look different.
+    fn fmt_item(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        seen: &mut Vec<*const ()>,
+    ) -> std::fmt::Result {
+        match self {
+            Value::String(string) => write!(f, "{:?}", string),
+            value => value.fmt_nested(f, seen),
+        }
+    }
+    /// `seen` has the lists and maps, that are being printed around this value. A list can contain
+    /// itself, `let a = [1]; a[0] = a`, so when one comes up again, it is only `[...]` or `{...}`.
+    fn fmt_nested(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        seen: &mut Vec<*const ()>,
+    ) -> std::fmt::Result {
+        match self {
+            Value::List(list) => {
+                let pointer = Rc::as_ptr(list) as *const ();
+                if seen.contains(&pointer) {
+                    return write!(f, "[...]");
+                }
+                seen.push(pointer);
+                write!(f, "[")?;
+                for (index, item) in list.borrow().iter().enumerate() {
+                    if index != 0 {
+                        write!(f, ", ")?;
+                    }
+                    item.fmt_item(f, seen)?;
+                }
+                seen.pop();
+                write!(f, "]")
+            }
+            Value::Map(map) => {
+                let pointer = Rc::as_ptr(map) as *const ();
+                if seen.contains(&pointer) {
+                    return write!(f, "{{...}}");
+                }
+                seen.push(pointer);
+                write!(f, "{{")?;
+                for (index, (key, value)) in map.borrow().iter().enumerate() {
+                    if index != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: ", key)?;
+                    value.fmt_item(f, seen)?;
+                }
+                seen.pop();
+                write!(f, "}}")
+            }
+            value => write!(f, "{}", value),
+        }
+    }
+}
+impl Key {
+    /// The value as a map key, if it can be one. The span is only for the error.
+    pub fn new(value: &Value, span: Span) -> Result<Key, RuntimeError> {
+        match value {
+            Value::Integer(num) => Ok(Key::Integer(*num)),
+            Value::BigInt(num) => Ok(Key::BigInt(num.clone())),
+            Value::String(string) => Ok(Key::String(string.clone())),
+            Value::Boolean(boolean) => Ok(Key::Boolean(*boolean)),
+            _ => Err(RuntimeError::KeyType {
+                found: value.type_name(),
+                span: span.into(),
+            }),
+        }
+    }
+}
+impl From<Key> for Value {
+    fn from(key: Key) -> Self {
+        match key {
+            Key::Integer(num) => Value::Integer(num),
+            Key::BigInt(num) => Value::BigInt(num),
+            Key::String(string) => Value::String(string),
+            Key::Boolean(boolean) => Value::Boolean(boolean),
+        }
+    }
+}
+/// Goes back to an `Integer`, if it fits.
+impl From<BigInt> for Value {
+    fn from(num: BigInt) -> Self {
+        match num.to_i64() {
+            Some(num) => Value::Integer(num),
+            None => Value::BigInt(Rc::new(num)),
+        }
+    }
+}
+impl From<Atomic> for Value {
+    /// Identifiers are not values, they are looked up by the evaluator before this.
+    fn from(atomic: Atomic) -> Self {
+        match atomic {
+            Atomic::Integer(num) => Value::Integer(num),
+            Atomic::BigInt(num) => Value::BigInt(num),
+            Atomic::Float(num) => Value::Float(num),
+            Atomic::String(string) => Value::String(string),
+            Atomic::Boolean(boolean) => Value::Boolean(boolean),
+            Atomic::Nil => Value::Nil,
+            Atomic::Identifier(_) => unreachable!("Identifiers are looked up, not converted."),
+        }
+    }
+}
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Integer(num) => write!(f, "{}", num),
+            Value::BigInt(num) => write!(f, "{}", num),
+            Value::Float(num) => write!(f, "{}", num),
+            Value::String(string) => write!(f, "{}", string),
+            Value::Boolean(boolean) => write!(f, "{}", boolean),
+            Value::Nil => write!(f, "()"),
+            Value::Function(function) => write!(f, "{:?}", function),
+            Value::Builtin(builtin) => write!(f, "{:?}", builtin),
+            Value::List(_) | Value::Map(_) => self.fmt_nested(f, &mut Vec::new()),
+            Value::Range(range) if range.inclusive => write!(f, "{}..={}", range.start, range.end),
+            Value::Range(range) => write!(f, "{}..{}", range.start, range.end),
+        }
+    }
+}
+/// Strings are quoted, like inside of a map.
+impl Display for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Key::Integer(num) => write!(f, "{}", num),
+            Key::BigInt(num) => write!(f, "{}", num),
+            Key::String(string) => write!(f, "{:?}", string),
+            Key::Boolean(boolean) => write!(f, "{}", boolean),
+        }
+    }
+}
+/// Not derived, the scope can contain the function itself.
+impl std::fmt::Debug for Function {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "<fn {}>", name),
+            None => write!(f, "<closure>"),
+        }
+    }
+}
@@ -1,13 +1,15 @@
-use core::panic;
-use std::{iter::Peekable, ops::Deref};
+use std::{borrow::Cow, fmt::Display, iter::Peekable, rc::Rc};
 
-use miette::{Diagnostic, NamedSource, SourceSpan};
+use miette::{Diagnostic, SourceSpan};
+use num_bigint::BigInt;
 use thiserror::Error;
 
 /// Input is the string that we need to Tokenize, basically the code.
 /// Index helps us keep track of each character, it is helpful at:
 ///     - Start of words.
 ///     - Error handling.
+/// It can be cloned, so the parser can look further ahead, without taking the tokens.
+#[derive(Clone)]
 pub struct Lexer<'a> {
     input: &'a str,
     index: usize,
@@ -22,28 +24,149 @@ impl<'a> Lexer<'a> {
             }
         };
 
-        return false;
+        false
+    }
+    /// Skips a `// comment` until the end of the line.
+    /// Called after the `//`, so `next_index` is right after it.
+    fn skip_line_comment(&mut self) {
+        self.index = match self.input[self.next_index..].find('\n') {
+            Some(offset) => self.next_index + offset,
+            None => self.input.len(),
+        };
+    }
+    /// Skips a `/* comment */`. They can be nested, so every `/*` needs its own `*/`.
+    /// Called after the opening `/*`, so `next_index` is right after it.
+    fn skip_block_comment(&mut self) -> Result<(), LexerError> {
+        let start = self.index;
+        let mut depth = 1;
+        let mut rest = self.input[self.next_index..].char_indices().peekable();
+        while let Some((_, character)) = rest.next() {
+            match (character, rest.peek()) {
+                ('/', Some((_, '*'))) => {
+                    rest.next();
+                    depth += 1;
+                }
+                ('*', Some((offset, '/'))) => {
+                    let end = self.next_index + offset + '/'.len_utf8();
+                    rest.next();
+                    depth -= 1;
+                    if depth == 0 {
+                        self.index = end;
+                        return Ok(());
+                    }
+                }
+                _ => (),
+            }
+        }
+        self.index = self.input.len();
+        Err(LexerError::UnfComment(LexerErrorStruct {
+            bad_bit: (start, self.index - start).into(),
+        }))
+    }
+
+    /// `r"..."` or `r#"..."#`, called when we are at the `r`.
+    fn is_raw_string(&self) -> bool {
+        self.input[self.next_index..]
+            .trim_start_matches('#')
+            .starts_with('"')
+    }
+    /// Raw strings have no escapes, they end at the first `"` that is followed by
+    /// as many `#` as there were at the start, so they can contain `"` too.
+    fn raw_string(&mut self) -> Result<Token<'a>, LexerError> {
+        let start = self.index;
+        let after_r = &self.input[self.next_index..];
+        let hashes = after_r.len() - after_r.trim_start_matches('#').len();
+        let body_start = self.next_index + hashes + '"'.len_utf8();
+        let closing = format!("\"{}", "#".repeat(hashes));
+
+        let Some(length) = self.input[body_start..].find(&closing) else {
+            self.index = self.input.len();
+            return Err(LexerError::UnfStr(LexerErrorStruct {
+                bad_bit: (start, self.index - start).into(),
+            }));
+        };
+        self.index = body_start + length + closing.len();
+        Ok(Token {
+            token_type: TokenType::String(Cow::Borrowed(
+                &self.input[body_start..body_start + length],
+            )),
+            start,
+            end: self.index,
+        })
+    }
+    /// Replaces the escape sequences in the body of a string.
+    /// `start` is where the body starts in the input, so the errors can point at the bad escape.
+    fn unescape(&self, start: usize, body: &'a str) -> Result<Cow<'a, str>, LexerError> {
+        if !body.contains('\\') {
+            return Ok(Cow::Borrowed(body));
+        }
+        let mut string = String::with_capacity(body.len());
+        let mut rest = body;
+        let mut offset = start;
+        while let Some(backslash) = rest.find('\\') {
+            string.push_str(&rest[..backslash]);
+            let escape_start = offset + backslash;
+            let after = &rest[backslash + '\\'.len_utf8()..];
+            // The character, and how long the escape is after the backslash.
+            let (character, length) = match after.chars().next() {
+                Some('n') => (Some('\n'), 1),
+                Some('t') => (Some('\t'), 1),
+                Some('r') => (Some('\r'), 1),
+                Some('0') => (Some('\0'), 1),
+                Some('\\') => (Some('\\'), 1),
+                Some('"') => (Some('"'), 1),
+                Some('\'') => (Some('\''), 1),
+                Some('u') => unicode_escape(after),
+                Some(other) => (None, other.len_utf8()),
+                None => (None, 0),
+            };
+            let Some(character) = character else {
+                return Err(LexerError::BadEscape(LexerErrorStruct {
+                    bad_bit: (escape_start, '\\'.len_utf8() + length).into(),
+                }));
+            };
+            string.push(character);
+            rest = &after[length..];
+            offset = escape_start + '\\'.len_utf8() + length;
+        }
+        string.push_str(rest);
+        Ok(Cow::Owned(string))
     }
 
     fn char_token(&mut self, token: TokenType<'a>) -> Option<Result<Token<'a>, LexerError>> {
         let start_index = self.index;
         self.index = self.next_index;
-        return Some(Ok(Token {
+        Some(Ok(Token {
             start: start_index,
             end: self.next_index,
             token_type: token,
-        }));
+        }))
     }
 
     pub fn new(input: &'a str) -> Lexer<'a> {
         Lexer {
-            input: input,
+            input,
             index: 0,
             next_index: 0,
         }
     }
 }
-#[derive(PartialEq, PartialOrd, Debug)]
+/// `u{1F335}`, the part of a `\u{...}` escape after the backslash.
+/// Returns the character, if it is valid, and the length of the escape.
+fn unicode_escape(after: &str) -> (Option<char>, usize) {
+    let Some(close) = after.find('}') else {
+        return (None, 'u'.len_utf8());
+    };
+    let Some(hex) = after[..close].strip_prefix("u{") else {
+        return (None, 'u'.len_utf8());
+    };
+    let character = match hex.len() {
+        1..=6 => u32::from_str_radix(hex, 16).ok().and_then(char::from_u32),
+        _ => None,
+    };
+    (character, close + '}'.len_utf8())
+}
+#[derive(PartialEq, PartialOrd, Debug, Clone)]
 pub struct Token<'a> {
     pub token_type: TokenType<'a>,
     pub start: usize,
@@ -51,10 +174,10 @@ pub struct Token<'a> {
 }
 impl Token<'_> {
     pub fn get_type(&self) -> TokenType<'_> {
-        return self.token_type.clone();
+        self.token_type.clone()
     }
 }
-#[derive(Error, Debug, Diagnostic)]
+#[derive(Error, Debug, Diagnostic, Clone)]
 #[error("Error while lexing.")]
 #[diagnostic(
     code(oops::my::bad),
@@ -62,31 +185,39 @@ impl Token<'_> {
     help("Contact the customer support... oh we don't have one!")
 )]
 pub struct LexerErrorStruct {
-    // The Source that we're gonna be printing snippets out of.
-    // This can be a String if you don't have or care about file names.
-    #[source_code]
-    src: String,
-    // Snippets and highlights can be included in the diagnostic!
+    // Only the location, the code is added when the error is printed.
     #[label("This bit here")]
     bad_bit: SourceSpan,
 }
-#[derive(Error, Debug, Diagnostic)]
+#[derive(Error, Debug, Diagnostic, Clone)]
+/// The variants only change the message, the location comes from the `LexerErrorStruct`.
 pub enum LexerError {
-    #[error("Error while lexing.")]
-    Failed(LexerErrorStruct),
+    #[diagnostic(forward(0))]
     #[error("Thanks one dot is enough!")]
     DotErr(LexerErrorStruct),
+    #[diagnostic(forward(0))]
     #[error("Unfinished string!")]
     UnfStr(LexerErrorStruct),
+    #[error("Invalid escape sequence!")]
+    #[diagnostic(
+        forward(0),
+        help("The escapes are \\n \\t \\r \\0 \\\\ \\\" \\' and \\u{{...}}, or use a raw string: r\"...\"")
+    )]
+    BadEscape(LexerErrorStruct),
+    #[error("Unfinished block comment!")]
+    #[diagnostic(forward(0), help("Every /* needs a */, even the nested ones."))]
+    UnfComment(LexerErrorStruct),
+    #[diagnostic(forward(0))]
     #[error("Error while parsing!")]
     ParsErr(LexerErrorStruct),
+    #[diagnostic(forward(0))]
     #[error("Unkown character!")]
     UnkChar(LexerErrorStruct),
 }
 /// This stores all the Tokens, that later will be parsed.
 /// To add a new Token, add it here, and then in State Machine of the next() function.
 /// If an identifier, it is enough to specify it in the Identifier state in the return.
-#[derive(PartialEq, PartialOrd, Debug, Clone, Copy)]
+#[derive(PartialEq, PartialOrd, Debug, Clone)]
 pub enum TokenType<'a> {
     /// (
     ParenLeft,
@@ -95,21 +226,36 @@ pub enum TokenType<'a> {
 
     BraceLeft,
     BraceRight,
+    BracketLeft,
+    BracketRight,
 
     GreatEqual,
     Great,
+    GreatGreat,
     LessEqual,
     Less,
+    LessLess,
     Equal,
     Bang,
     BangEqual,
     SemiColon, // Colon :DD
+    Colon,
+    Comma,
+    Dot,
+    /// `..` and `..=`, the ranges.
+    DotDot,
+    DotDotEqual,
     EqualEqual,
 
-    String(&'a str),
-    Integer(i32),
-    Float(f32),
+    /// The escapes are already replaced, so it only borrows if there were none.
+    String(Cow<'a, str>),
+    Integer(i64),
+    /// An integer literal, that doesn't fit in an `i64`.
+    BigInt(Rc<BigInt>),
+    Float(f64),
     Identifier(&'a str),
+    /// `'outer`, the name of a loop, without the `'`.
+    Label(&'a str),
 
     Plus,
     PlusEqual,
@@ -118,19 +264,35 @@ pub enum TokenType<'a> {
     MinusEqual,
     Decrement,
     Star,
+    StarStar,
+    StarEqual,
     Slash,
+    SlashEqual,
+    /// `~/`, integer division.
+    TildeSlash,
+    Percent,
+    Ampersand,
+    Caret,
+    Tilde,
 
     True,
     False,
 
     And,
     Or,
+    /// `|`, around the parameters of a closure: `|a, b| a + b`.
+    Pipe,
 
     If,
     Else,
     While,
+    For,
+    In,
+    Break,
+    Continue,
     Let,
     Return,
+    Fn,
 }
 
 #[derive(PartialEq, Debug)]
@@ -139,40 +301,84 @@ enum State {
     Integer,
     Float,
     StartString,
+    /// After a `\` in a string, the next character can't end the string.
+    Escape,
     EndString,
     Identifier,
+    Label,
 }
 pub trait Check {
-    fn expect(&mut self, token: TokenType) -> bool;
     fn maybe_expect(&mut self, token: TokenType) -> bool;
-    fn panic_expect(&mut self, token: TokenType) -> bool;
 }
 
 impl<'a> Check for Peekable<Lexer<'a>> {
-    fn expect(&mut self, token: TokenType) -> bool {
-        match self.peek() {
-            Some(Ok(t)) if t.get_type() == token => {
-                self.next();
-                true
-            }
-            _ => false,
-        }
-    }
-
     fn maybe_expect(&mut self, token: TokenType) -> bool {
-        match self.peek() {
-            Some(Ok(t)) if t.get_type() == token => true,
-            _ => false,
-        }
+        matches!(self.peek(), Some(Ok(t)) if t.get_type() == token)
     }
-
-    fn panic_expect(&mut self, token: TokenType) -> bool {
-        match self.peek() {
-            Some(Ok(t)) if t.get_type() == token => {
-                self.next();
-                true
-            }
-            _ => panic!("No {:?} was found!", token),
+}
+/// How the token looks in the code, used in the error messages.
+impl Display for TokenType<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenType::ParenLeft => write!(f, "("),
+            TokenType::ParenRight => write!(f, ")"),
+            TokenType::BraceLeft => write!(f, "{{"),
+            TokenType::BraceRight => write!(f, "}}"),
+            TokenType::BracketLeft => write!(f, "["),
+            TokenType::BracketRight => write!(f, "]"),
+            TokenType::GreatEqual => write!(f, ">="),
+            TokenType::Great => write!(f, ">"),
+            TokenType::GreatGreat => write!(f, ">>"),
+            TokenType::LessEqual => write!(f, "<="),
+            TokenType::Less => write!(f, "<"),
+            TokenType::LessLess => write!(f, "<<"),
+            TokenType::Equal => write!(f, "="),
+            TokenType::Bang => write!(f, "!"),
+            TokenType::BangEqual => write!(f, "!="),
+            TokenType::SemiColon => write!(f, ";"),
+            TokenType::Colon => write!(f, ":"),
+            TokenType::Comma => write!(f, ","),
+            TokenType::Dot => write!(f, "."),
+            TokenType::DotDot => write!(f, ".."),
+            TokenType::DotDotEqual => write!(f, "..="),
+            TokenType::EqualEqual => write!(f, "=="),
+            TokenType::String(string) => write!(f, "\"{}\"", string),
+            TokenType::Integer(num) => write!(f, "{}", num),
+            TokenType::BigInt(num) => write!(f, "{}", num),
+            TokenType::Float(num) => write!(f, "{}", num),
+            TokenType::Identifier(name) => write!(f, "{}", name),
+            TokenType::Label(name) => write!(f, "'{}", name),
+            TokenType::Plus => write!(f, "+"),
+            TokenType::PlusEqual => write!(f, "+="),
+            TokenType::Increment => write!(f, "++"),
+            TokenType::Minus => write!(f, "-"),
+            TokenType::MinusEqual => write!(f, "-="),
+            TokenType::Decrement => write!(f, "--"),
+            TokenType::Star => write!(f, "*"),
+            TokenType::StarStar => write!(f, "**"),
+            TokenType::StarEqual => write!(f, "*="),
+            TokenType::Slash => write!(f, "/"),
+            TokenType::SlashEqual => write!(f, "/="),
+            TokenType::TildeSlash => write!(f, "~/"),
+            TokenType::Percent => write!(f, "%"),
+            TokenType::Ampersand => write!(f, "&"),
+            TokenType::Caret => write!(f, "^"),
+            TokenType::Tilde => write!(f, "~"),
+            TokenType::True => write!(f, "true"),
+            TokenType::False => write!(f, "false"),
+            TokenType::And => write!(f, "&&"),
+            TokenType::Or => write!(f, "||"),
+            TokenType::Pipe => write!(f, "|"),
+            TokenType::If => write!(f, "if"),
+            TokenType::Else => write!(f, "else"),
+            TokenType::While => write!(f, "while"),
+            TokenType::For => write!(f, "for"),
+            TokenType::In => write!(f, "in"),
+            TokenType::Break => write!(f, "break"),
+            TokenType::Continue => write!(f, "continue"),
+            TokenType::Let => write!(f, "let"),
+            TokenType::Return => write!(f, "return"),
+            TokenType::Fn => write!(f, "fn"),
         }
     }
 }
@@ -187,11 +393,7 @@ impl<'a> Iterator for Lexer<'a> {
         let mut state = State::Unknown;
 
         // Search token bounds.
-        loop {
-            let Some(character) = characters.next() else {
-                break;
-            };
-
+        while let Some(character) = characters.next() {
             self.next_index = self.index + character.len_utf8();
 
             //println!("State: {:?} Current: \"{}\" Character: \'{}\'", state, &self.input[from_to..self.next_index], character);
@@ -201,7 +403,7 @@ impl<'a> Iterator for Lexer<'a> {
                         self.index = self.next_index;
                         continue;
                     }
-                    State::StartString => (),
+                    State::StartString | State::Escape => (),
                     _ => break,
                 }
             }
@@ -210,8 +412,10 @@ impl<'a> Iterator for Lexer<'a> {
                 State::Unknown => {
                     from_to = self.index;
                     match character {
+                        '>' if self.expect('>') => return self.char_token(TokenType::GreatGreat),
                         '>' if self.expect('=') => return self.char_token(TokenType::GreatEqual),
                         '>' => return self.char_token(TokenType::Great),
+                        '<' if self.expect('<') => return self.char_token(TokenType::LessLess),
                         '<' if self.expect('=') => return self.char_token(TokenType::LessEqual),
                         '<' => return self.char_token(TokenType::Less),
                         '+' if self.expect('+') => return self.char_token(TokenType::Increment),
@@ -226,38 +430,77 @@ impl<'a> Iterator for Lexer<'a> {
                         '!' => return self.char_token(TokenType::Bang),
                         '=' if self.expect('=') => return self.char_token(TokenType::EqualEqual),
                         '&' if self.expect('&') => return self.char_token(TokenType::And),
+                        '&' => return self.char_token(TokenType::Ampersand),
+                        '^' => return self.char_token(TokenType::Caret),
+                        // `//` is a comment, so integer division is `~/`.
+                        '~' if self.expect('/') => return self.char_token(TokenType::TildeSlash),
+                        '~' => return self.char_token(TokenType::Tilde),
+                        '%' => return self.char_token(TokenType::Percent),
                         '|' if self.expect('|') => return self.char_token(TokenType::Or),
+                        '|' => return self.char_token(TokenType::Pipe),
                         '=' => return self.char_token(TokenType::Equal),
                         ';' => return self.char_token(TokenType::SemiColon),
+                        ':' => return self.char_token(TokenType::Colon),
+                        '.' if self.expect('.') => {
+                            if self.expect('=') {
+                                return self.char_token(TokenType::DotDotEqual);
+                            }
+                            return self.char_token(TokenType::DotDot);
+                        }
+                        '.' => return self.char_token(TokenType::Dot),
+                        ',' => return self.char_token(TokenType::Comma),
                         '{' => return self.char_token(TokenType::BraceLeft),
                         '}' => return self.char_token(TokenType::BraceRight),
+                        '[' => return self.char_token(TokenType::BracketLeft),
+                        ']' => return self.char_token(TokenType::BracketRight),
+                        '*' if self.expect('*') => return self.char_token(TokenType::StarStar),
+                        '*' if self.expect('=') => return self.char_token(TokenType::StarEqual),
                         '*' => return self.char_token(TokenType::Star),
+                        '/' if self.expect('/') => {
+                            self.skip_line_comment();
+                            characters = self.input[self.index..].chars();
+                            continue;
+                        }
+                        '/' if self.expect('*') => {
+                            if let Err(err) = self.skip_block_comment() {
+                                return Some(Err(err));
+                            }
+                            characters = self.input[self.index..].chars();
+                            continue;
+                        }
+                        '/' if self.expect('=') => return self.char_token(TokenType::SlashEqual),
                         '/' => return self.char_token(TokenType::Slash),
                         '0'..='9' => state = State::Integer,
+                        'r' if self.is_raw_string() => return Some(self.raw_string()),
                         '_' | 'A'..='Z' | 'a'..='z' => state = State::Identifier,
+                        '\'' => state = State::Label,
                         '"' => state = State::StartString,
                         _ => {
+                            // Skip it, so the next token can be lexed after it.
+                            self.index = self.next_index;
                             return Some(Err(LexerError::UnkChar(LexerErrorStruct {
-                                src: self.input.into(),
                                 bad_bit: (from_to, self.index - from_to).into(),
-                            })))
+                            })));
                         }
                     }
                 }
                 State::Integer => match character {
                     '0'..='9' => (),
+                    // `1..2` is a range, not a float.
+                    '.' if characters.clone().next() == Some('.') => break,
                     '.' => state = State::Float,
                     _ => break,
                 },
                 State::Float => {
                     match character {
                         '0'..='9' => (),
+                        '.' if characters.clone().next() == Some('.') => break,
                         // Two or more dots!
                         '.' => {
+                            self.index = self.next_index;
                             return Some(Err(LexerError::DotErr(LexerErrorStruct {
-                                src: self.input.into(),
                                 bad_bit: (from_to, self.index - from_to).into(),
-                            })))
+                            })));
                         }
                         _ => break,
                     }
@@ -266,13 +509,16 @@ impl<'a> Iterator for Lexer<'a> {
                     // This means we have reached the second "" which means the string is final.
                     if character == '"' {
                         state = State::EndString;
-                        //Some(Token::String(&self.input[from_to + '\"'.len_utf8()..self.index - '\"'.len_utf8()]))
+                    } else if character == '\\' {
+                        state = State::Escape;
                     }
                 }
+                // What it means is checked when the string is finished.
+                State::Escape => state = State::StartString,
                 State::EndString => {
                     break;
                 }
-                State::Identifier => match character {
+                State::Identifier | State::Label => match character {
                     '_' | 'A'..='Z' | 'a'..='z' | '0'..='9' => (),
                     _ => break,
                 },
@@ -287,53 +533,252 @@ impl<'a> Iterator for Lexer<'a> {
 
         let token_type = match state {
             State::Unknown => None,
-            State::Integer => match &self.input[from_to..self.index].parse::<i32>() {
+            State::Integer => match &self.input[from_to..self.index].parse::<i64>() {
                 Ok(num) => Some(TokenType::Integer(*num)),
-                Err(e) => {
-                    return Some(Err(LexerError::ParsErr(LexerErrorStruct {
-                        src: self.input.into(),
-                        bad_bit: (from_to, self.index - from_to).into(),
-                    })))
+                // Only digits, so it can only fail because it is too big.
+                Err(_) => {
+                    let num = self.input[from_to..self.index]
+                        .parse::<BigInt>()
+                        .expect("Digits are always a valid BigInt.");
+                    Some(TokenType::BigInt(Rc::new(num)))
                 }
             },
-            State::Float => match &self.input[from_to..self.index].parse::<f32>() {
+            State::Float => match &self.input[from_to..self.index].parse::<f64>() {
                 Ok(num) => Some(TokenType::Float(*num)),
-                Err(e) => {
+                Err(_) => {
                     return Some(Err(LexerError::ParsErr(LexerErrorStruct {
-                        src: self.input.into(),
                         bad_bit: (from_to, self.index - from_to).into(),
                     })))
                 }
             },
-            State::StartString => {
+            State::StartString | State::Escape => {
                 return Some(Err(LexerError::UnfStr(LexerErrorStruct {
-                    src: self.input.into(),
                     bad_bit: (from_to, self.index - from_to).into(),
                 })))
             }
-            State::EndString => Some(TokenType::String(
-                &self.input[from_to + '\"'.len_utf8()..self.index - '\"'.len_utf8()],
-            )),
+            State::EndString => {
+                let start = from_to + '"'.len_utf8();
+                let body = &self.input[start..self.index - '"'.len_utf8()];
+                match self.unescape(start, body) {
+                    Ok(string) => Some(TokenType::String(string)),
+                    Err(err) => return Some(Err(err)),
+                }
+            }
             State::Identifier => match &self.input[from_to..self.index] {
                 "if" => Some(TokenType::If),
                 "else" => Some(TokenType::Else),
                 "while" => Some(TokenType::While),
+                "for" => Some(TokenType::For),
+                "in" => Some(TokenType::In),
+                "break" => Some(TokenType::Break),
+                "continue" => Some(TokenType::Continue),
                 "let" => Some(TokenType::Let),
                 "return" => Some(TokenType::Return),
+                "fn" => Some(TokenType::Fn),
                 "true" => Some(TokenType::True),
                 "false" => Some(TokenType::False),
                 id => Some(TokenType::Identifier(id)),
             },
+            State::Label => match &self.input[from_to + '\''.len_utf8()..self.index] {
+                // A `'` without a name after it.
+                "" => {
+                    return Some(Err(LexerError::UnkChar(LexerErrorStruct {
+                        bad_bit: (from_to, self.index - from_to).into(),
+                    })))
+                }
+                name => Some(TokenType::Label(name)),
+            },
         };
         match token_type {
-            Some(val) => {
-                return Some(Ok(Token {
-                    start: from_to,
-                    end: self.index,
-                    token_type: val,
-                }))
-            }
-            None => return None,
+            Some(val) => Some(Ok(Token {
+                start: from_to,
+                end: self.index,
+                token_type: val,
+            })),
+            None => None,
         }
     }
 }
+#[cfg(test)]
+mod tests {
+    use super::{Lexer, LexerError, TokenType};
+    use num_bigint::BigInt;
+    use std::{borrow::Cow, rc::Rc};
+
+    fn types(input: &str) -> Vec<TokenType<'_>> {
+        Lexer::new(input)
+            .map(|token| token.unwrap().token_type)
+            .collect()
+    }
+    #[test]
+    fn line_comments() {
+        assert_eq!(
+            types("1 // one\n/ 2 //"),
+            vec![
+                TokenType::Integer(1),
+                TokenType::Slash,
+                TokenType::Integer(2)
+            ]
+        );
+    }
+    #[test]
+    fn assignment_operators() {
+        assert_eq!(
+            types("a += 1 -= *= /= ++ -- /"),
+            vec![
+                TokenType::Identifier("a"),
+                TokenType::PlusEqual,
+                TokenType::Integer(1),
+                TokenType::MinusEqual,
+                TokenType::StarEqual,
+                TokenType::SlashEqual,
+                TokenType::Increment,
+                TokenType::Decrement,
+                TokenType::Slash
+            ]
+        );
+    }
+    #[test]
+    fn operators() {
+        assert_eq!(
+            types("% ** *= ~/ ~ & && | ^ << <= >> >="),
+            vec![
+                TokenType::Percent,
+                TokenType::StarStar,
+                TokenType::StarEqual,
+                TokenType::TildeSlash,
+                TokenType::Tilde,
+                TokenType::Ampersand,
+                TokenType::And,
+                TokenType::Pipe,
+                TokenType::Caret,
+                TokenType::LessLess,
+                TokenType::LessEqual,
+                TokenType::GreatGreat,
+                TokenType::GreatEqual
+            ]
+        );
+    }
+    #[test]
+    fn fields() {
+        assert_eq!(
+            types("m.a: 1.5"),
+            vec![
+                TokenType::Identifier("m"),
+                TokenType::Dot,
+                TokenType::Identifier("a"),
+                TokenType::Colon,
+                TokenType::Float(1.5)
+            ]
+        );
+    }
+    #[test]
+    fn ranges() {
+        assert_eq!(
+            types("for i in 0..10 1.5..=2."),
+            vec![
+                TokenType::For,
+                TokenType::Identifier("i"),
+                TokenType::In,
+                TokenType::Integer(0),
+                TokenType::DotDot,
+                TokenType::Integer(10),
+                TokenType::Float(1.5),
+                TokenType::DotDotEqual,
+                TokenType::Float(2.0)
+            ]
+        );
+    }
+    #[test]
+    fn labels() {
+        assert_eq!(
+            types("'outer: while break 'outer continue"),
+            vec![
+                TokenType::Label("outer"),
+                TokenType::Colon,
+                TokenType::While,
+                TokenType::Break,
+                TokenType::Label("outer"),
+                TokenType::Continue
+            ]
+        );
+        assert!(matches!(
+            Lexer::new("' a").next(),
+            Some(Err(LexerError::UnkChar(_)))
+        ));
+    }
+    #[test]
+    fn big_integers() {
+        assert_eq!(
+            types("9223372036854775807 9223372036854775808"),
+            vec![
+                TokenType::Integer(i64::MAX),
+                TokenType::BigInt(Rc::new(BigInt::from(i64::MAX) + 1))
+            ]
+        );
+    }
+    #[test]
+    fn block_comments() {
+        assert_eq!(
+            types("1 /* a /* nested */ still * / comment */ + /**/2"),
+            vec![
+                TokenType::Integer(1),
+                TokenType::Plus,
+                TokenType::Integer(2)
+            ]
+        );
+    }
+    #[test]
+    fn unfinished_block_comment() {
+        let tokens: Vec<_> = Lexer::new("1 /* a /* b */").collect();
+
+        assert_eq!(tokens.len(), 2);
+        assert!(matches!(&tokens[1], Err(LexerError::UnfComment(_))));
+    }
+    #[test]
+    fn escapes() {
+        assert_eq!(
+            types(r#""a\"b" "tab\tnew\nline\\" "\u{1F335}\u{e9}" "plain""#),
+            vec![
+                TokenType::String("a\"b".into()),
+                TokenType::String("tab\tnew\nline\\".into()),
+                TokenType::String("🌵é".into()),
+                TokenType::String("plain".into()),
+            ]
+        );
+        // Only the strings with escapes need a new allocation.
+        assert!(matches!(
+            types(r#""plain""#)[0],
+            TokenType::String(Cow::Borrowed(_))
+        ));
+    }
+    #[test]
+    fn bad_escapes() {
+        for (input, offset, length) in [
+            (r#""ab\qc""#, 3, 2),
+            (r#""\u{110000}""#, 1, 10),
+            (r#""\u{zz}""#, 1, 6),
+            (r#""\u12""#, 1, 2),
+        ] {
+            let tokens: Vec<_> = Lexer::new(input).collect();
+            let Some(Err(LexerError::BadEscape(error))) = tokens.first() else {
+                panic!("{} should be a bad escape", input);
+            };
+            assert_eq!(error.bad_bit, (offset, length).into(), "{}", input);
+        }
+    }
+    #[test]
+    fn raw_strings() {
+        assert_eq!(
+            types(r###"r"\d+\." r#"say "hi""# r ra"###),
+            vec![
+                TokenType::String(r"\d+\.".into()),
+                TokenType::String(r#"say "hi""#.into()),
+                TokenType::Identifier("r"),
+                TokenType::Identifier("ra"),
+            ]
+        );
+        let tokens: Vec<_> = Lexer::new(r###"r#"no end""###).collect();
+        assert!(matches!(tokens[..], [Err(LexerError::UnfStr(_))]));
+    }
+}
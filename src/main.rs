@@ -1,7 +1,12 @@
-use clap::{command, Subcommand};
+use clap::Subcommand;
 use evaluate::Evaluator;
-use lexer::{Lexer, Token};
-use std::{fs, path::PathBuf};
+use lexer::Lexer;
+use miette::{Diagnostic, NamedSource, Report};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
 
 use std::io::{self, Write};
 mod evaluate;
@@ -10,17 +15,101 @@ mod parse;
 #[derive(clap::Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
+    /// Without a command, it starts the interactive shell.
     #[command(subcommand)]
-    command: Commands,
+    command: Option<Commands>,
 }
 #[derive(Subcommand, Debug)]
 enum Commands {
+    /// Prints the tokens of the file.
     Tokenize { filename: PathBuf },
+    /// Prints the syntax tree of the file.
     Parse { filename: PathBuf },
+    /// Runs the file.
     Run { filename: PathBuf },
 }
-fn main() {
-    //let file_contents = fs::read_to_string(r#"C:\Users\tothk\Documents\Rust\script-language\test.simp"#).expect("Opening file failed!");
+/// The code could not be lexed or parsed.
+const EXIT_SYNTAX: u8 = 65;
+/// The file could not be read.
+const EXIT_NO_INPUT: u8 = 66;
+/// The program failed while running.
+const EXIT_RUNTIME: u8 = 70;
+
+fn main() -> ExitCode {
+    let args = <Args as clap::Parser>::parse();
+
+    let Some(command) = args.command else {
+        repl();
+        return ExitCode::SUCCESS;
+    };
+    let filename = match &command {
+        Commands::Tokenize { filename }
+        | Commands::Parse { filename }
+        | Commands::Run { filename } => filename,
+    };
+    let file_contents = match fs::read_to_string(filename) {
+        Ok(contents) => contents,
+        Err(err) => {
+            eprintln!("Opening {} failed: {}", filename.display(), err);
+            return ExitCode::from(EXIT_NO_INPUT);
+        }
+    };
+
+    match command {
+        Commands::Tokenize { .. } => {
+            let mut failed = false;
+            for token in Lexer::new(&file_contents) {
+                match token {
+                    Ok(token) => println!("{:?}", token),
+                    Err(err) => {
+                        report(err, filename, &file_contents);
+                        failed = true;
+                    }
+                }
+            }
+            if failed {
+                return ExitCode::from(EXIT_SYNTAX);
+            }
+        }
+        Commands::Parse { .. } => match parse::Parser::new(&file_contents).parse() {
+            Ok(program) => println!("{}", program),
+            Err(errors) => {
+                for error in errors {
+                    report(error, filename, &file_contents);
+                }
+                return ExitCode::from(EXIT_SYNTAX);
+            }
+        },
+        Commands::Run { .. } => {
+            let program = match parse::Parser::new(&file_contents).parse() {
+                Ok(program) => program,
+                Err(errors) => {
+                    for error in errors {
+                        report(error, filename, &file_contents);
+                    }
+                    return ExitCode::from(EXIT_SYNTAX);
+                }
+            };
+            match Evaluator::new().run(program) {
+                Ok(evaluate::Value::Nil) => (),
+                Ok(value) => println!("{}", value),
+                Err(err) => {
+                    report(err, filename, &file_contents);
+                    return ExitCode::from(EXIT_RUNTIME);
+                }
+            }
+        }
+    }
+    ExitCode::SUCCESS
+}
+/// Prints the error with the bit of the file it points to, and the name of the file.
+fn report(error: impl Diagnostic + Send + Sync + 'static, filename: &Path, code: &str) {
+    let source = NamedSource::new(filename.display().to_string(), code.to_string());
+    eprintln!("{:?}", Report::new(error).with_source_code(source));
+}
+/// Reads one line at a time, and runs it with the same evaluator,
+/// so the variables are kept between the lines. Errors are printed, but don't stop it.
+fn repl() {
     let mut eval = Evaluator::new();
     loop {
         let mut input = String::new();
@@ -28,31 +117,29 @@ fn main() {
         print!(">>> "); // print the prompt
         io::stdout().flush().expect("Failed to flush stdout"); // flush to ensure it appears immediately
 
-        io::stdin()
+        let read = io::stdin()
             .read_line(&mut input)
             .expect("Failed to read line");
-
-        let wasd = parse::Parser::new(&input).parse();
-        if let Some(value) = eval.evaluate(wasd) {
-            println!("{}", value);
+        // End of the input, for example Ctrl+D.
+        if read == 0 {
+            println!();
+            return;
         }
-        
-    }
-    
 
-    
-    
-    
-    return;
-    let args = <Args as clap::Parser>::parse();
-
-    match args.command {
-        Commands::Tokenize { filename } => {
-            let file_contents = fs::read_to_string(filename).expect("Opening file failed!");
-            for token in Lexer::new(&file_contents) {
-                println!("{:?}", token);
+        let wasd = match parse::Parser::new(&input).parse() {
+            Ok(program) => program,
+            Err(errors) => {
+                for error in errors {
+                    println!("{:?}", Report::new(error).with_source_code(input.clone()));
+                }
+                continue;
             }
+        };
+        // Like `run`, nil is not printed, so `let a = 1;` doesn't print anything.
+        match eval.run(wasd) {
+            Ok(evaluate::Value::Nil) => (),
+            Ok(value) => println!("{}", value),
+            Err(err) => println!("{:?}", Report::new(err).with_source_code(input)),
         }
-        _ => println!("Semmi!"),
     }
 }
@@ -1,12 +1,45 @@
 use super::*;
 
-impl<'a> Display for TokenTree<'a> {
+impl Display for Program {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (index, statement) in self.statements.iter().enumerate() {
+            if index != 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", statement)?;
+        }
+        Ok(())
+    }
+}
+impl Display for Statement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Statement::Semi(tree) => write!(f, "{};", tree),
+            Statement::Expression(tree) => write!(f, "{}", tree),
+        }
+    }
+}
+impl Display for TokenTree {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            TokenTree::Atomic(atom) => write!(f, "{}", atom),
-            TokenTree::InfixExpression(op, token_tree)
-            | TokenTree::PostfixExpression(op, token_tree)
-            | TokenTree::PrefixExpression(op, token_tree) => {
+            TokenTree::Atomic(atom, _) => write!(f, "{}", atom),
+            TokenTree::Map(entries, _) => {
+                write!(f, "(map")?;
+                for (key, value) in entries {
+                    write!(f, " ({} {})", key, value)?;
+                }
+                write!(f, ")")
+            }
+            TokenTree::List(items, _) => {
+                write!(f, "(list")?;
+                for item in items {
+                    write!(f, " {}", item)?;
+                }
+                write!(f, ")")
+            }
+            TokenTree::InfixExpression(op, token_tree, _)
+            | TokenTree::PostfixExpression(op, token_tree, _)
+            | TokenTree::PrefixExpression(op, token_tree, _) => {
                 write!(f, "({}", op)?;
                 for tree in token_tree {
                     write!(f, " {}", tree)?;
@@ -17,6 +50,7 @@ impl<'a> Display for TokenTree<'a> {
                 condition,
                 positive,
                 negative,
+                ..
             } => {
                 write!(f, "(if")?;
                 write!(f, " {}", condition)?;
@@ -27,11 +61,71 @@ impl<'a> Display for TokenTree<'a> {
                 }
                 write!(f, ")")
             }
-            TokenTree::While { condition, body } => {
+            TokenTree::While {
+                label,
+                condition,
+                body,
+                ..
+            } => {
                 write!(f, "(while")?;
+                if let Some(label) = label {
+                    write!(f, " '{}", label)?;
+                }
                 write!(f, " {}", condition)?;
-                write!(f, " : {}", body)
+                write!(f, " : {})", body)
+            }
+            TokenTree::For {
+                label,
+                variable,
+                iterable,
+                body,
+                ..
+            } => {
+                write!(f, "(for")?;
+                if let Some(label) = label {
+                    write!(f, " '{}", label)?;
+                }
+                write!(f, " {} {} : {})", variable, iterable, body)
+            }
+            TokenTree::Break(label, _) | TokenTree::Continue(label, _) => {
+                let keyword = match self {
+                    TokenTree::Break(..) => "break",
+                    _ => "continue",
+                };
+                match label {
+                    Some(label) => write!(f, "({} '{})", keyword, label),
+                    None => write!(f, "({})", keyword),
+                }
+            }
+            TokenTree::Function {
+                name,
+                parameters,
+                body,
+                ..
+            } => {
+                match name {
+                    Some(name) => write!(f, "(fn {} (", name)?,
+                    None => write!(f, "(fn (")?,
+                }
+                for (index, parameter) in parameters.iter().enumerate() {
+                    if index != 0 {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "{}", parameter)?;
+                }
+                write!(f, ") : {})", body)
+            }
+            TokenTree::Block(statements, _) => {
+                write!(f, "(block")?;
+                for statement in statements {
+                    write!(f, " {}", statement)?;
+                }
+                write!(f, ")")
             }
+            TokenTree::Return(value, _) => match value {
+                Some(value) => write!(f, "(return {})", value),
+                None => write!(f, "(return)"),
+            },
         }
     }
 }
@@ -42,9 +136,25 @@ impl Display for Operator {
             Operator::Plus => write!(f, "+"),
             Operator::Star => write!(f, "*"),
             Operator::Bang => write!(f, "!"),
+            Operator::Not => write!(f, "not"),
             Operator::Let => write!(f, "let"),
             Operator::Assign => write!(f, "="),
+            Operator::PlusAssign => write!(f, "+="),
+            Operator::MinusAssign => write!(f, "-="),
+            Operator::StarAssign => write!(f, "*="),
+            Operator::SlashAssign => write!(f, "/="),
+            Operator::Increment => write!(f, "++"),
+            Operator::Decrement => write!(f, "--"),
             Operator::Slash => write!(f, "/"),
+            Operator::Modulo => write!(f, "%"),
+            Operator::Power => write!(f, "**"),
+            Operator::IntDivide => write!(f, "~/"),
+            Operator::BitAnd => write!(f, "&"),
+            Operator::BitOr => write!(f, "|"),
+            Operator::BitXor => write!(f, "^"),
+            Operator::ShiftLeft => write!(f, "<<"),
+            Operator::ShiftRight => write!(f, ">>"),
+            Operator::BitNot => write!(f, "~"),
             Operator::Greater => write!(f, ">"),
             Operator::GreaterOrEqual => write!(f, ">="),
             Operator::Less => write!(f, "<"),
@@ -53,19 +163,23 @@ impl Display for Operator {
             Operator::NotEqual => write!(f, "!="),
             Operator::And => write!(f, "&&"),
             Operator::Or => write!(f, "||"),
-            
+            Operator::Range => write!(f, ".."),
+            Operator::RangeInclusive => write!(f, "..="),
+            Operator::Call => write!(f, "call"),
+            Operator::Index => write!(f, "index"),
         }
     }
 }
-impl<'a> Display for Atomic<'a> {
+impl Display for Atomic {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Atomic::Float(num) => write!(f, "{}", num),
             Atomic::String(string) => write!(f, "{}", string),
             Atomic::Integer(num) => write!(f, "{}", num),
+            Atomic::BigInt(num) => write!(f, "{}", num),
             Atomic::Nil => write!(f, "()"),
             Atomic::Identifier(name) => write!(f, "{}", name),
-            Atomic::Boolean(boolean) => write!(f, "{}", boolean)
+            Atomic::Boolean(boolean) => write!(f, "{}", boolean),
         }
     }
 }
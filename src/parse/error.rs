@@ -0,0 +1,50 @@
+use miette::{Diagnostic, SourceSpan};
+use thiserror::Error;
+
+use crate::lexer::LexerError;
+
+/// The spans point into the code, that was parsed, it is given to miette when the error is printed,
+/// like for the `RuntimeError`, so the errors don't each carry a copy of it.
+#[derive(Error, Debug, Diagnostic)]
+pub enum ParseError {
+    #[error("Expected {expected}, found {found}.")]
+    UnexpectedToken {
+        expected: String,
+        found: String,
+        #[label("This bit here")]
+        bad_bit: SourceSpan,
+    },
+    #[error("Expected {expected}, but the code ended.")]
+    UnexpectedEnd {
+        expected: String,
+        #[label("Here")]
+        bad_bit: SourceSpan,
+    },
+    #[error("{operator} can't be used as a prefix operator.")]
+    BadPrefix {
+        operator: String,
+        #[label("This bit here")]
+        bad_bit: SourceSpan,
+    },
+    #[error("This can't be changed!")]
+    #[diagnostic(help("Only variables can be assigned to, or changed with += and ++."))]
+    NotAssignable {
+        #[label("This can't be assigned to")]
+        bad_bit: SourceSpan,
+    },
+    #[error("`{keyword}` can only be used inside of a loop.")]
+    OutsideLoop {
+        keyword: String,
+        #[label("This bit here")]
+        bad_bit: SourceSpan,
+    },
+    #[error("There is no loop called '{label} around this.")]
+    UnknownLabel {
+        label: String,
+        #[label("This bit here")]
+        bad_bit: SourceSpan,
+    },
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Lexer(#[from] LexerError),
+}
@@ -1,19 +1,55 @@
-use std::{fmt::Display, iter::Peekable, vec};
+use miette::SourceSpan;
+use num_bigint::BigInt;
+use std::{fmt::Display, iter::Peekable, rc::Rc};
 mod display;
-use crate::lexer::{self, Check, Lexer, LexerError, Token, TokenType};
+mod error;
+use crate::lexer::{Check, Lexer, LexerError, Token, TokenType};
+pub use error::ParseError;
 /// Needed because of lifetime complications.
 pub struct Parser<'a> {
+    input: &'a str,
     lexer: Peekable<Lexer<'a>>,
+    /// Where the last token we took ended, used for the error spans.
+    last_end: usize,
+    /// The labels of the loops around the current token, the innermost is last.
+    /// A function starts with none, a `break` can't leave it.
+    loops: Vec<Option<Rc<str>>>,
+    /// The errors of the broken statements, that were skipped, so the parsing could go on.
+    errors: Vec<ParseError>,
 }
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 
 pub enum Operator {
     Minus,
     Plus,
     Star,
     Slash,
+    /// `%`, the remainder, it has the sign of the left side.
+    Modulo,
+    /// `**`, the only right associative one: `2 ** 3 ** 2` is `2 ** 9`.
+    Power,
+    /// `~/`, divides and drops the fraction, so the result is always an integer.
+    IntDivide,
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
+    /// Prefix `~`, flips every bit.
+    BitNot,
+    /// Postfix `!`, the factorial.
     Bang,
+    /// Prefix `!`, the logical not.
+    Not,
     Assign,
+    /// `+=`, and the ones below, change the variable with the value on the right.
+    PlusAssign,
+    MinusAssign,
+    StarAssign,
+    SlashAssign,
+    /// `++` and `--`, as a prefix they give the new value, as a postfix the old one.
+    Increment,
+    Decrement,
 
     Greater,
     GreaterOrEqual,
@@ -24,55 +60,420 @@ pub enum Operator {
 
     And,
     Or,
+    /// `a..b` leaves out `b`, `a..=b` doesn't.
+    Range,
+    RangeInclusive,
 
     Let,
+    /// `f(a, b)`, the callee and the arguments are the children.
+    Call,
+    /// `a[i]`, the list and the index are the children.
+    Index,
 }
-#[derive(Clone, Copy, Debug)]
+/// The tree owns its strings, so a function body can outlive the code it was parsed from.
+#[derive(Clone, Debug)]
 
-pub enum Atomic<'a> {
-    Integer(i32),
-    String(&'a str),
-    Float(f32),
-    Identifier(&'a str),
+pub enum Atomic {
+    Integer(i64),
+    BigInt(Rc<BigInt>),
+    String(Rc<str>),
+    Float(f64),
+    Identifier(Rc<str>),
     Boolean(bool),
     Nil,
 }
+/// A whole script, the statements in the order they were written.
+pub struct Program {
+    pub statements: Vec<Statement>,
+}
 #[derive(Clone)]
-pub enum TokenTree<'a> {
-    Atomic(Atomic<'a>),
+pub enum Statement {
+    /// Expression closed by a `;`, its value is thrown away.
+    Semi(TokenTree),
+    /// Expression without a `;`, if it is the last one, it is the value of the whole program.
+    Expression(TokenTree),
+}
+/// Where a part of the tree is in the code, from the first character to the end of the last token.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+impl From<Span> for SourceSpan {
+    fn from(span: Span) -> Self {
+        (span.start, span.end - span.start).into()
+    }
+}
+#[derive(Clone)]
+pub enum TokenTree {
+    Atomic(Atomic, Span),
+    /// `[1, 2, 3]`
+    List(Vec<TokenTree>, Span),
+    /// `{ "a": 1, b: 2 }`, the keys and the values in the order they were written.
+    Map(Vec<(TokenTree, TokenTree)>, Span),
     // [TokenTree; 2]????
-    InfixExpression(Operator, Vec<TokenTree<'a>>),
-    PostfixExpression(Operator, Vec<TokenTree<'a>>),
-    PrefixExpression(Operator, Vec<TokenTree<'a>>),
+    InfixExpression(Operator, Vec<TokenTree>, Span),
+    PostfixExpression(Operator, Vec<TokenTree>, Span),
+    PrefixExpression(Operator, Vec<TokenTree>, Span),
     If {
         // We have to store them on the Heap,
         // because otherwise it has infinite size.
         // Something that we cannot store on the Stack.
-        condition: Box<TokenTree<'a>>,
-        positive: Box<TokenTree<'a>>,
-        negative: Option<Box<TokenTree<'a>>>,
+        condition: Box<TokenTree>,
+        positive: Box<TokenTree>,
+        negative: Option<Box<TokenTree>>,
+        span: Span,
     },
+    /// `'label: while condition { body }`, the label is optional, like for the `for`.
     While {
-        condition: Box<TokenTree<'a>>,
-        body: Box<TokenTree<'a>>,
+        label: Option<Rc<str>>,
+        condition: Box<TokenTree>,
+        body: Box<TokenTree>,
+        span: Span,
+    },
+    /// `for name in iterable { body }`
+    For {
+        label: Option<Rc<str>>,
+        variable: Rc<str>,
+        iterable: Box<TokenTree>,
+        body: Box<TokenTree>,
+        span: Span,
     },
+    /// `fn name(a, b) { body }`, the body is shared with the function values made from it.
+    /// Anonymous functions, `fn(a) { body }` and `|a| body`, have no name,
+    /// and they are values instead of declarations.
+    Function {
+        name: Option<Rc<str>>,
+        parameters: Vec<Rc<str>>,
+        body: Rc<TokenTree>,
+        span: Span,
+    },
+    /// `{ a; b }`, the statements in their own scope.
+    /// Its value is the last statement's, if that has no `;` after it, otherwise nil.
+    Block(Vec<Statement>, Span),
+    /// `return value`, or just `return`, which returns nil.
+    Return(Option<Box<TokenTree>>, Span),
+    /// `break`, or `break 'label` to leave an outer loop.
+    Break(Option<Rc<str>>, Span),
+    /// `continue`, or `continue 'label` to start the next round of an outer loop.
+    Continue(Option<Rc<str>>, Span),
 }
 impl<'a> Parser<'a> {
-    pub fn new(input: &str) -> Parser {
+    pub fn new(input: &str) -> Parser<'_> {
         Parser {
+            input,
             lexer: Lexer::new(input).peekable(),
+            last_end: 0,
+            loops: Vec::new(),
+            errors: Vec::new(),
         }
     }
-    pub fn parse(mut self) -> TokenTree<'a> {
-        self.expression(0)
+    /// Parses the whole program. If a statement is broken, we note the error and skip
+    /// to the start of the next statement, so we can report every error in one go.
+    pub fn parse(mut self) -> Result<Program, Vec<ParseError>> {
+        let mut statements = Vec::new();
+        while self.lexer.peek().is_some() {
+            // Empty statements, like `;;`.
+            if self.expect(TokenType::SemiColon) {
+                continue;
+            }
+            match self.statement() {
+                Ok(statement) => statements.push(statement),
+                Err(error) => {
+                    self.errors.push(error);
+                    self.synchronize(false);
+                }
+            }
+        }
+        if self.errors.is_empty() {
+            Ok(Program { statements })
+        } else {
+            Err(self.errors)
+        }
     }
-    fn expected_token_parse(&mut self, token: TokenType) -> TokenTree<'a> {
-        if self.lexer.expect(token) {
-            TokenTree::Atomic(Atomic::Nil)
+    /// Skips tokens until the end of the broken statement (the next `;`),
+    /// or until a keyword, that starts a new one. The blocks inside of it are skipped whole.
+    /// In a block it stops before the `}`, that closes it, so the block can end there.
+    /// Lexer errors on the way are still reported.
+    fn synchronize(&mut self, in_block: bool) {
+        let mut depth: usize = 0;
+        while let Some(next) = self.lexer.peek() {
+            match next {
+                Ok(token)
+                    if depth == 0
+                        && matches!(
+                            token.token_type,
+                            TokenType::Let
+                                | TokenType::If
+                                | TokenType::While
+                                | TokenType::For
+                                | TokenType::Label(_)
+                                | TokenType::Return
+                                | TokenType::Fn
+                        ) =>
+                {
+                    return
+                }
+                Ok(token) if depth == 0 && token.token_type == TokenType::SemiColon => {
+                    self.next();
+                    return;
+                }
+                Ok(token) if token.token_type == TokenType::BraceLeft => {
+                    depth += 1;
+                    self.next();
+                }
+                Ok(token) if token.token_type == TokenType::BraceRight => {
+                    if depth == 0 && in_block {
+                        return;
+                    }
+                    depth = depth.saturating_sub(1);
+                    self.next();
+                }
+                Ok(_) => {
+                    self.next();
+                }
+                Err(_) => {
+                    if let Some(Err(error)) = self.next() {
+                        self.errors.push(error.into());
+                    }
+                }
+            }
+        }
+    }
+    /// Semicolons separate the statements, but they are optional after the last one
+    /// (of the program, or of a block), and after the ones that end with a block (`if`, `while`).
+    fn statement(&mut self) -> Result<Statement, ParseError> {
+        let tree = if self.starts_with_block() {
+            self.operand()?
         } else {
-            let val = self.expression(0);
-            self.lexer.panic_expect(token);
-            val
+            self.expression(0)?
+        };
+        if self.expect(TokenType::SemiColon) {
+            return Ok(Statement::Semi(tree));
+        }
+        let last = self.lexer.peek().is_none() || self.lexer.maybe_expect(TokenType::BraceRight);
+        if !last && !tree.ends_with_block() {
+            self.require(TokenType::SemiColon)?;
+        }
+        Ok(Statement::Expression(tree))
+    }
+    /// Every token goes through here, so we know where the last one ended.
+    fn next(&mut self) -> Option<Result<Token<'a>, LexerError>> {
+        let next = self.lexer.next();
+        if let Some(Ok(token)) = &next {
+            self.last_end = token.end;
+        }
+        next
+    }
+    /// Takes the next token if it is the one we want.
+    fn expect(&mut self, token: TokenType) -> bool {
+        if self.lexer.maybe_expect(token) {
+            self.next();
+            return true;
+        }
+        false
+    }
+    /// The next token has to be this one, otherwise it is a syntax error.
+    fn require(&mut self, token: TokenType) -> Result<Token<'a>, ParseError> {
+        match self.next() {
+            Some(Ok(next)) if next.token_type == token => Ok(next),
+            next => Err(self.unexpected(next, format!("`{}`", token))),
+        }
+    }
+    /// Error for a token (or the end of the code), that we did not expect here.
+    fn unexpected(
+        &self,
+        found: Option<Result<Token<'a>, LexerError>>,
+        expected: String,
+    ) -> ParseError {
+        match found {
+            Some(Ok(token)) => ParseError::UnexpectedToken {
+                expected,
+                found: format!("`{}`", token.token_type),
+                bad_bit: (token.start, token.end - token.start).into(),
+            },
+            Some(Err(error)) => error.into(),
+            None => ParseError::UnexpectedEnd {
+                expected,
+                bad_bit: (self.input.len(), 0).into(),
+            },
+        }
+    }
+    /// From `start` to the end of the last token we took.
+    fn span_from(&self, start: usize) -> Span {
+        Span {
+            start,
+            end: self.last_end,
+        }
+    }
+    /// Comma separated items until `close`, a trailing comma is allowed.
+    /// Called after the opening token, and takes the closing one too.
+    fn list<T>(
+        &mut self,
+        close: TokenType,
+        mut item: impl FnMut(&mut Self) -> Result<T, ParseError>,
+    ) -> Result<Vec<T>, ParseError> {
+        let mut items = Vec::new();
+        while !self.expect(close.clone()) {
+            items.push(item(self)?);
+            if !self.expect(TokenType::Comma) {
+                self.require(close)?;
+                break;
+            }
+        }
+        Ok(items)
+    }
+    /// `key: value` in a map.
+    fn entry(&mut self) -> Result<(TokenTree, TokenTree), ParseError> {
+        let key = match self.next() {
+            Some(Ok(token)) => {
+                let span = Span {
+                    start: token.start,
+                    end: token.end,
+                };
+                match map_key(token.get_type()) {
+                    Some(key) => TokenTree::Atomic(key, span),
+                    None => return Err(self.unexpected(Some(Ok(token)), "a key".into())),
+                }
+            }
+            next => return Err(self.unexpected(next, "a key".into())),
+        };
+        self.require(TokenType::Colon)?;
+        let value = self.expression(0)?;
+        Ok((key, value))
+    }
+    /// The name after the `.` in `a.b`, it becomes a string.
+    fn field(&mut self) -> Result<TokenTree, ParseError> {
+        match self.next() {
+            Some(Ok(Token {
+                token_type: TokenType::Identifier(name),
+                start,
+                end,
+            })) => Ok(TokenTree::Atomic(
+                Atomic::String(name.into()),
+                Span { start, end },
+            )),
+            next => Err(self.unexpected(next, "the name of a field".into())),
+        }
+    }
+    /// One parameter of a function, just a name.
+    fn parameter(&mut self) -> Result<Rc<str>, ParseError> {
+        match self.next() {
+            Some(Ok(Token {
+                token_type: TokenType::Identifier(name),
+                ..
+            })) => Ok(name.into()),
+            next => Err(self.unexpected(next, "a parameter".into())),
+        }
+    }
+    /// The body of a loop, the `break` and `continue` inside it go to this loop.
+    fn loop_body(&mut self, label: Option<Rc<str>>) -> Result<TokenTree, ParseError> {
+        self.loops.push(label);
+        let body = self.block();
+        self.loops.pop();
+        body
+    }
+    /// The body of a function, the loops around it can't be left from inside of it.
+    fn function_body(
+        &mut self,
+        body: impl FnOnce(&mut Self) -> Result<TokenTree, ParseError>,
+    ) -> Result<TokenTree, ParseError> {
+        let loops = std::mem::take(&mut self.loops);
+        let body = body(self);
+        self.loops = loops;
+        body
+    }
+    /// We want to see a condition, and after that a block. Maybe an else, and another block,
+    /// or another if, that is the whole else branch, so `else if` chains can be as long as needed.
+    fn if_expression(&mut self, start: usize) -> Result<TokenTree, ParseError> {
+        let condition = self.expression(0)?;
+
+        let positive = self.block()?;
+
+        let negative = if self.expect(TokenType::Else) {
+            let val = match self.lexer.peek() {
+                Some(Ok(Token {
+                    token_type: TokenType::If,
+                    start,
+                    ..
+                })) => {
+                    let start = *start;
+                    self.next();
+                    self.if_expression(start)?
+                }
+                _ => self.block()?,
+            };
+            Some(Box::new(val))
+        } else {
+            None
+        };
+        Ok(TokenTree::If {
+            condition: Box::new(condition),
+            positive: Box::new(positive),
+            negative,
+            span: self.span_from(start),
+        })
+    }
+    fn while_loop(
+        &mut self,
+        label: Option<Rc<str>>,
+        start: usize,
+    ) -> Result<TokenTree, ParseError> {
+        let condition = self.expression(0)?;
+        let body = self.loop_body(label.clone())?;
+
+        Ok(TokenTree::While {
+            label,
+            condition: Box::new(condition),
+            body: Box::new(body),
+            span: self.span_from(start),
+        })
+    }
+    fn for_loop(&mut self, label: Option<Rc<str>>, start: usize) -> Result<TokenTree, ParseError> {
+        let variable = match self.next() {
+            Some(Ok(Token {
+                token_type: TokenType::Identifier(name),
+                ..
+            })) => name.into(),
+            next => return Err(self.unexpected(next, "the name of the loop variable".into())),
+        };
+        self.require(TokenType::In)?;
+        let iterable = self.expression(0)?;
+        let body = self.loop_body(label.clone())?;
+
+        Ok(TokenTree::For {
+            label,
+            variable,
+            iterable: Box::new(iterable),
+            body: Box::new(body),
+            span: self.span_from(start),
+        })
+    }
+    /// The optional label after a `break` or `continue`, it has to belong to a loop around it.
+    /// Without one, it goes to the innermost loop, but there has to be one.
+    fn jump_label(&mut self, keyword: &str, start: usize) -> Result<Option<Rc<str>>, ParseError> {
+        let label = match self.lexer.peek() {
+            Some(Ok(Token {
+                token_type: TokenType::Label(label),
+                ..
+            })) => {
+                let label: Rc<str> = (*label).into();
+                self.next();
+                Some(label)
+            }
+            _ => None,
+        };
+        let bad_bit = (start, self.last_end - start).into();
+        match &label {
+            None if self.loops.is_empty() => Err(ParseError::OutsideLoop {
+                keyword: keyword.into(),
+                bad_bit,
+            }),
+            Some(name) if !self.loops.contains(&label) => Err(ParseError::UnknownLabel {
+                label: name.to_string(),
+                bad_bit,
+            }),
+            _ => Ok(label),
         }
     }
     // 1 + (2 * 2 * 2) + 2
@@ -81,88 +482,252 @@ impl<'a> Parser<'a> {
     // 1     *
     //     2    -
     //          2
-    fn syntax(&mut self, left_side: TokenType, right_side: TokenType) -> TokenTree<'a> {
-        self.lexer.panic_expect(left_side);
-        self.expected_token_parse(right_side)
+    fn expected_token_parse(&mut self, token: TokenType) -> Result<TokenTree, ParseError> {
+        let start = self.last_end;
+        if self.expect(token.clone()) {
+            Ok(TokenTree::Atomic(Atomic::Nil, self.span_from(start)))
+        } else {
+            let val = self.expression(0)?;
+            self.require(token)?;
+            Ok(val)
+        }
+    }
+    /// `{ statements }`, the bodies of `if`, loops and functions.
+    /// Here `{}` is an empty block, in an expression it would be an empty map.
+    fn block(&mut self) -> Result<TokenTree, ParseError> {
+        let start = self.require(TokenType::BraceLeft)?.start;
+        self.block_statements(start)
+    }
+    /// The statements of a block until its `}`, called after the `{`.
+    /// A broken statement is skipped like at the top level, the rest of the block is still parsed.
+    fn block_statements(&mut self, start: usize) -> Result<TokenTree, ParseError> {
+        let mut statements = Vec::new();
+        while !self.expect(TokenType::BraceRight) {
+            if self.lexer.peek().is_none() {
+                return Err(self.unexpected(None, "`}`".into()));
+            }
+            if self.expect(TokenType::SemiColon) {
+                continue;
+            }
+            match self.statement() {
+                Ok(statement) => statements.push(statement),
+                Err(error) => {
+                    self.errors.push(error);
+                    self.synchronize(true);
+                }
+            }
+        }
+        Ok(TokenTree::Block(statements, self.span_from(start)))
     }
-    fn expression(&mut self, min_bp: u8) -> TokenTree<'a> {
-        let token = match self.lexer.next() {
+    /// `if`, loops, blocks and named functions are whole statements on their own, like in Rust,
+    /// so a `(`, `[` or `-` on the next line starts a new statement, instead of continuing them.
+    fn starts_with_block(&self) -> bool {
+        let mut ahead = self.lexer.clone();
+        let Some(Ok(token)) = ahead.next() else {
+            return false;
+        };
+        match token.token_type {
+            TokenType::If | TokenType::While | TokenType::For | TokenType::Label(_) => true,
+            TokenType::BraceLeft => !is_map(ahead),
+            TokenType::Fn => matches!(
+                ahead.next(),
+                Some(Ok(Token {
+                    token_type: TokenType::Identifier(_),
+                    ..
+                }))
+            ),
+            _ => false,
+        }
+    }
+    fn expression(&mut self, min_bp: u8) -> Result<TokenTree, ParseError> {
+        let start = match self.lexer.peek() {
+            Some(Ok(token)) => token.start,
+            _ => self.last_end,
+        };
+        let lhs = self.operand()?;
+        self.operators(lhs, start, min_bp)
+    }
+    /// Everything, that doesn't have something on its left: literals, prefix operators,
+    /// and the keywords, like `if`.
+    fn operand(&mut self) -> Result<TokenTree, ParseError> {
+        let token = match self.next() {
             Some(Ok(token)) => token,
-            Some(Err(error)) => return TokenTree::Atomic(Atomic::Nil),
-            None => return TokenTree::Atomic(Atomic::Nil),
+            next => return Err(self.unexpected(next, "an expression".into())),
         };
+        let start = token.start;
         //println!("{:?}", token.get_type());
         // Short for Left Hand Side.
-        let mut lhs: TokenTree = match token.token_type {
-            TokenType::Float(num) => TokenTree::Atomic(Atomic::Float(num)),
-            TokenType::Integer(num) => TokenTree::Atomic(Atomic::Integer(num)),
-            TokenType::String(string) => TokenTree::Atomic(Atomic::String(string)),
-            TokenType::Identifier(name) => TokenTree::Atomic(Atomic::Identifier(name)),
-            TokenType::True => TokenTree::Atomic(Atomic::Boolean(true)),
-            TokenType::False => TokenTree::Atomic(Atomic::Boolean(false)),
-            TokenType::Plus | TokenType::Minus => {
+        let lhs: TokenTree = match token.token_type {
+            TokenType::Float(num) => TokenTree::Atomic(Atomic::Float(num), self.span_from(start)),
+            TokenType::Integer(num) => {
+                TokenTree::Atomic(Atomic::Integer(num), self.span_from(start))
+            }
+            TokenType::BigInt(num) => TokenTree::Atomic(Atomic::BigInt(num), self.span_from(start)),
+            TokenType::String(string) => {
+                TokenTree::Atomic(Atomic::String(string.into()), self.span_from(start))
+            }
+            TokenType::Identifier(name) => {
+                TokenTree::Atomic(Atomic::Identifier(name.into()), self.span_from(start))
+            }
+            TokenType::True => TokenTree::Atomic(Atomic::Boolean(true), self.span_from(start)),
+            TokenType::False => TokenTree::Atomic(Atomic::Boolean(false), self.span_from(start)),
+            TokenType::Plus
+            | TokenType::Minus
+            | TokenType::Bang
+            | TokenType::Tilde
+            | TokenType::Increment
+            | TokenType::Decrement => {
                 let operator = match token.get_type() {
                     TokenType::Plus => Operator::Plus,
+                    TokenType::Bang => Operator::Not,
+                    TokenType::Tilde => Operator::BitNot,
                     TokenType::Minus => Operator::Minus,
+                    TokenType::Increment => Operator::Increment,
+                    TokenType::Decrement => Operator::Decrement,
                     _ => unreachable!("This is impossible to reach."),
                 };
 
-                let ((), right_bp) = prefix_binding_power(&operator);
-                let rhs = self.expression(right_bp);
-                TokenTree::PrefixExpression(operator, vec![rhs])
+                let Some(((), right_bp)) = prefix_binding_power(&operator) else {
+                    return Err(ParseError::BadPrefix {
+                        operator: operator.to_string(),
+                        bad_bit: (token.start, token.end - token.start).into(),
+                    });
+                };
+                let rhs = self.expression(right_bp)?;
+                if operator.assigns() && !rhs.is_assignable() {
+                    let span = rhs.span();
+                    return Err(ParseError::NotAssignable {
+                        bad_bit: span.into(),
+                    });
+                }
+                TokenTree::PrefixExpression(operator, vec![rhs], self.span_from(start))
+            }
+            TokenType::ParenLeft => self.expected_token_parse(TokenType::ParenRight)?,
+            TokenType::BracketLeft => {
+                let items = self.list(TokenType::BracketRight, |parser| parser.expression(0))?;
+                TokenTree::List(items, self.span_from(start))
             }
-            TokenType::ParenLeft => {
-                let lhs = self.expected_token_parse(TokenType::ParenRight);
-                lhs
+            TokenType::BraceLeft if is_map(self.lexer.clone()) => {
+                let entries = self.list(TokenType::BraceRight, Self::entry)?;
+                TokenTree::Map(entries, self.span_from(start))
             }
+            TokenType::BraceLeft => self.block_statements(start)?,
             TokenType::Let => {
-                let lhs = self.expression(0);
-                TokenTree::PrefixExpression(Operator::Let, vec![lhs])
+                let lhs = self.expression(0)?;
+                TokenTree::PrefixExpression(Operator::Let, vec![lhs], self.span_from(start))
             }
-            TokenType::If => {
-                // We want to see a condition, and after that a block. Maybe an else, and another block.
-                let condition = self.expression(0);
-
-                let positive = self.syntax(TokenType::BraceLeft, TokenType::BraceRight);
-
-                let negative = if self.lexer.expect(TokenType::Else) {
-                    let val = self.syntax(TokenType::BraceLeft, TokenType::BraceRight);
-                    Some(Box::new(val))
-                } else {
-                    None
+            TokenType::If => self.if_expression(start)?,
+            TokenType::While => self.while_loop(None, start)?,
+            TokenType::For => self.for_loop(None, start)?,
+            TokenType::Label(label) => {
+                self.require(TokenType::Colon)?;
+                match self.next() {
+                    Some(Ok(Token {
+                        token_type: TokenType::While,
+                        ..
+                    })) => self.while_loop(Some(label.into()), start)?,
+                    Some(Ok(Token {
+                        token_type: TokenType::For,
+                        ..
+                    })) => self.for_loop(Some(label.into()), start)?,
+                    next => return Err(self.unexpected(next, "a loop after the label".into())),
+                }
+            }
+            TokenType::Break => {
+                let label = self.jump_label("break", start)?;
+                TokenTree::Break(label, self.span_from(start))
+            }
+            TokenType::Continue => {
+                let label = self.jump_label("continue", start)?;
+                TokenTree::Continue(label, self.span_from(start))
+            }
+            TokenType::Fn => {
+                // Without a name, it is an anonymous function: `fn(a) { a }`.
+                let name = match self.next() {
+                    Some(Ok(Token {
+                        token_type: TokenType::Identifier(name),
+                        ..
+                    })) => {
+                        self.require(TokenType::ParenLeft)?;
+                        Some(name.into())
+                    }
+                    Some(Ok(Token {
+                        token_type: TokenType::ParenLeft,
+                        ..
+                    })) => None,
+                    next => return Err(self.unexpected(next, "the name of the function".into())),
                 };
-                TokenTree::If {
-                    condition: Box::new(condition),
-                    positive: Box::new(positive),
-                    negative: negative,
+                let parameters = self.list(TokenType::ParenRight, Self::parameter)?;
+                let body = self.function_body(Self::block)?;
+
+                TokenTree::Function {
+                    name,
+                    parameters,
+                    body: Rc::new(body),
+                    span: self.span_from(start),
                 }
             }
-            TokenType::While => {
-                let condition = self.expression(0);
-                let body = self.syntax(TokenType::BraceLeft, TokenType::BraceRight);
+            // Closures, `|a, b| a + b`, and `|| 1` without parameters.
+            // The body is everything to the right, like with `let`.
+            TokenType::Pipe | TokenType::Or => {
+                let parameters = if token.token_type == TokenType::Pipe {
+                    self.list(TokenType::Pipe, Self::parameter)?
+                } else {
+                    Vec::new()
+                };
+                let body = self.function_body(|parser| parser.expression(0))?;
 
-                TokenTree::While {
-                    condition: Box::new(condition),
-                    body: Box::new(body),
+                TokenTree::Function {
+                    name: None,
+                    parameters,
+                    body: Rc::new(body),
+                    span: self.span_from(start),
                 }
             }
-            t => panic!("Bad token on left hand side. {:?}", t),
+            TokenType::Return => {
+                // A bare `return` is followed by something, that can't start an expression.
+                let value = match self.lexer.peek() {
+                    None
+                    | Some(Ok(Token {
+                        token_type:
+                            TokenType::SemiColon | TokenType::BraceRight | TokenType::ParenRight,
+                        ..
+                    })) => None,
+                    _ => Some(Box::new(self.expression(0)?)),
+                };
+                TokenTree::Return(value, self.span_from(start))
+            }
+            _ => return Err(self.unexpected(Some(Ok(token)), "an expression".into())),
         };
-
-        loop {
-            // We peek, because it is recursive.
-            // If this fails, we go back to the parent, but that parent is still in a loop.
-            // 4D chess.
-            let Some(Ok(token)) = self.lexer.peek() else {
-                break;
-            };
-
+        Ok(lhs)
+    }
+    /// The infix and postfix operators after `lhs`, as long as they bind tighter than `min_bp`.
+    fn operators(
+        &mut self,
+        mut lhs: TokenTree,
+        start: usize,
+        min_bp: u8,
+    ) -> Result<TokenTree, ParseError> {
+        // We peek, because it is recursive.
+        // If this fails, we go back to the parent, but that parent is still in a loop.
+        // 4D chess.
+        while let Some(Ok(token)) = self.lexer.peek() {
+            // `a.b` is the same as `a["b"]`.
+            let field = token.token_type == TokenType::Dot;
             let operator = match token.get_type() {
                 TokenType::Plus => Operator::Plus,
                 TokenType::Minus => Operator::Minus,
                 TokenType::Star => Operator::Star,
                 TokenType::Bang => Operator::Bang,
                 TokenType::Slash => Operator::Slash,
+                TokenType::Percent => Operator::Modulo,
+                TokenType::StarStar => Operator::Power,
+                TokenType::TildeSlash => Operator::IntDivide,
+                TokenType::Ampersand => Operator::BitAnd,
+                TokenType::Pipe => Operator::BitOr,
+                TokenType::Caret => Operator::BitXor,
+                TokenType::LessLess => Operator::ShiftLeft,
+                TokenType::GreatGreat => Operator::ShiftRight,
 
                 TokenType::BangEqual => Operator::NotEqual,
                 TokenType::EqualEqual => Operator::EqualEqual,
@@ -172,19 +737,27 @@ impl<'a> Parser<'a> {
                 TokenType::LessEqual => Operator::LessOrEqual,
                 TokenType::And => Operator::And,
                 TokenType::Or => Operator::Or,
+                TokenType::DotDot => Operator::Range,
+                TokenType::DotDotEqual => Operator::RangeInclusive,
+                TokenType::ParenLeft => Operator::Call,
+                TokenType::BracketLeft | TokenType::Dot => Operator::Index,
 
-                TokenType::Equal => {
-                    if let TokenTree::Atomic(Atomic::Identifier(_)) = lhs {
-                        Operator::Assign
-                    } else {
-                        panic!("Left hand side is not an identifier!")
-                    }
-                }
-                t => {
-                    //println!("Unidetified operator: {:?}", t);
+                TokenType::Equal => Operator::Assign,
+                TokenType::PlusEqual => Operator::PlusAssign,
+                TokenType::MinusEqual => Operator::MinusAssign,
+                TokenType::StarEqual => Operator::StarAssign,
+                TokenType::SlashEqual => Operator::SlashAssign,
+                TokenType::Increment => Operator::Increment,
+                TokenType::Decrement => Operator::Decrement,
+                _ => {
                     break;
                 }
             };
+            if operator.assigns() && !lhs.is_assignable() {
+                return Err(ParseError::NotAssignable {
+                    bad_bit: (start, self.last_end - start).into(),
+                });
+            }
             // Left hand binding power, and Right hand binding power.
             // If we have a special character and parsing folds, because of the break at end,
             // we get the Expression for further processing.For example parsing an if statement:
@@ -197,9 +770,20 @@ impl<'a> Parser<'a> {
                 }
                 // We can now iterate, because we know that this specific Token checks out,
                 // so we can move down another layer and check if we can collapse.
-                self.lexer.next();
+                self.next();
 
-                lhs = TokenTree::PostfixExpression(operator, vec![lhs]);
+                let mut children = vec![lhs];
+                match operator {
+                    Operator::Call => children
+                        .extend(self.list(TokenType::ParenRight, |parser| parser.expression(0))?),
+                    Operator::Index if field => children.push(self.field()?),
+                    Operator::Index => {
+                        children.push(self.expression(0)?);
+                        self.require(TokenType::BracketRight)?;
+                    }
+                    _ => (),
+                }
+                lhs = TokenTree::PostfixExpression(operator, children, self.span_from(start));
                 continue;
             }
             if let Some((left_bp, right_bp)) = infix_binding_power(&operator) {
@@ -208,59 +792,584 @@ impl<'a> Parser<'a> {
                 }
                 // We can now iterate, because we know that this specific Token checks out,
                 // so we can move down another layer and check if we can collapse.
-                self.lexer.next();
+                self.next();
 
                 // Short of Right Hand Side
-                let rhs = self.expression(right_bp);
+                let rhs = self.expression(right_bp)?;
 
-                lhs = TokenTree::InfixExpression(operator, vec![lhs, rhs]);
+                lhs = TokenTree::InfixExpression(operator, vec![lhs, rhs], self.span_from(start));
                 continue;
             }
             break;
         }
 
-        lhs
+        Ok(lhs)
+    }
+}
+impl TokenTree {
+    pub fn span(&self) -> Span {
+        match self {
+            TokenTree::Atomic(_, span)
+            | TokenTree::List(_, span)
+            | TokenTree::Map(_, span)
+            | TokenTree::InfixExpression(_, _, span)
+            | TokenTree::PostfixExpression(_, _, span)
+            | TokenTree::PrefixExpression(_, _, span)
+            | TokenTree::If { span, .. }
+            | TokenTree::While { span, .. }
+            | TokenTree::For { span, .. }
+            | TokenTree::Function { span, .. }
+            | TokenTree::Block(_, span)
+            | TokenTree::Return(_, span)
+            | TokenTree::Break(_, span)
+            | TokenTree::Continue(_, span) => *span,
+        }
+    }
+    /// Can be on the left of an `=`, or changed by `+=` and `++`.
+    fn is_assignable(&self) -> bool {
+        matches!(
+            self,
+            TokenTree::Atomic(Atomic::Identifier(_), _)
+                | TokenTree::PostfixExpression(Operator::Index, _, _)
+        )
+    }
+    /// These don't need a `;` to be separated from the next statement.
+    fn ends_with_block(&self) -> bool {
+        matches!(
+            self,
+            TokenTree::If { .. }
+                | TokenTree::While { .. }
+                | TokenTree::For { .. }
+                | TokenTree::Block(..)
+                | TokenTree::Function { name: Some(_), .. }
+        )
+    }
+}
+impl Operator {
+    /// The ones that change a variable, so they need something assignable.
+    fn assigns(&self) -> bool {
+        matches!(
+            self,
+            Operator::Assign
+                | Operator::PlusAssign
+                | Operator::MinusAssign
+                | Operator::StarAssign
+                | Operator::SlashAssign
+                | Operator::Increment
+                | Operator::Decrement
+        )
     }
 }
+/// After a `{`, a map starts with `}` or with `key:`, anything else would be a block.
+/// It looks ahead on a copy of the lexer, so the tokens are still there for the parser.
+fn is_map<'a>(mut ahead: impl Iterator<Item = Result<Token<'a>, LexerError>>) -> bool {
+    match ahead.next() {
+        Some(Ok(Token {
+            token_type: TokenType::BraceRight,
+            ..
+        })) => true,
+        Some(Ok(token)) if map_key(token.get_type()).is_some() => matches!(
+            ahead.next(),
+            Some(Ok(Token {
+                token_type: TokenType::Colon,
+                ..
+            }))
+        ),
+        _ => false,
+    }
+}
+/// The literals, that can be the keys in a map literal. A name is the same as a string,
+/// so `{ a: 1 }` is `{ "a": 1 }`.
+fn map_key(token: TokenType) -> Option<Atomic> {
+    match token {
+        TokenType::Identifier(name) => Some(Atomic::String(name.into())),
+        TokenType::String(string) => Some(Atomic::String(string.into())),
+        TokenType::Integer(num) => Some(Atomic::Integer(num)),
+        TokenType::BigInt(num) => Some(Atomic::BigInt(num)),
+        TokenType::True => Some(Atomic::Boolean(true)),
+        TokenType::False => Some(Atomic::Boolean(false)),
+        _ => None,
+    }
+}
+#[derive(Clone, Copy, PartialEq)]
+enum Fixity {
+    /// `a - b - c` is `(a - b) - c`.
+    Left,
+    /// `a = b = c` is `a = (b = c)`.
+    Right,
+    Prefix,
+    Postfix,
+}
+/// The whole precedence grammar, every operator from the loosest level to the tightest.
+/// The binding powers come from the position in here, so a new level is just a new line.
+/// Prefix operators are tighter than `*`, but looser than `**`, so `-2 ** 2` is `-(2 ** 2)`.
+const PRECEDENCE: &[(Fixity, &[Operator])] = &[
+    (
+        Fixity::Right,
+        &[
+            Operator::Assign,
+            Operator::PlusAssign,
+            Operator::MinusAssign,
+            Operator::StarAssign,
+            Operator::SlashAssign,
+        ],
+    ),
+    (Fixity::Left, &[Operator::Range, Operator::RangeInclusive]),
+    (Fixity::Left, &[Operator::Or]),
+    (Fixity::Left, &[Operator::And]),
+    (Fixity::Left, &[Operator::EqualEqual, Operator::NotEqual]),
+    (
+        Fixity::Left,
+        &[
+            Operator::Less,
+            Operator::LessOrEqual,
+            Operator::Greater,
+            Operator::GreaterOrEqual,
+        ],
+    ),
+    (Fixity::Left, &[Operator::BitOr]),
+    (Fixity::Left, &[Operator::BitXor]),
+    (Fixity::Left, &[Operator::BitAnd]),
+    (Fixity::Left, &[Operator::ShiftLeft, Operator::ShiftRight]),
+    (Fixity::Left, &[Operator::Plus, Operator::Minus]),
+    (
+        Fixity::Left,
+        &[
+            Operator::Star,
+            Operator::Slash,
+            Operator::IntDivide,
+            Operator::Modulo,
+        ],
+    ),
+    (
+        Fixity::Prefix,
+        &[
+            Operator::Plus,
+            Operator::Minus,
+            Operator::Not,
+            Operator::BitNot,
+            Operator::Increment,
+            Operator::Decrement,
+        ],
+    ),
+    (Fixity::Right, &[Operator::Power]),
+    (
+        Fixity::Postfix,
+        &[
+            Operator::Bang,
+            Operator::Increment,
+            Operator::Decrement,
+            Operator::Call,
+            Operator::Index,
+        ],
+    ),
+];
+/// The binding power of the operator's level, and whether it groups to the left or the right.
+/// The powers are odd, so the infix ones have room for one more on one side.
+fn level(operator: &Operator, fixity: impl Fn(Fixity) -> bool) -> Option<(u8, Fixity)> {
+    PRECEDENCE
+        .iter()
+        .zip((1..).step_by(2))
+        .find(|((found, operators), _)| fixity(*found) && operators.contains(operator))
+        .map(|((found, _), power)| (power, *found))
+}
 /// Operator has two atomic neighbours.
 fn infix_binding_power(operator: &Operator) -> Option<(u8, u8)> {
-    match operator {
-        Operator::Minus | Operator::Plus => Some((2, 3)),
-        Operator::Assign => Some((1, 2)),
-        Operator::Star | Operator::Slash => Some((4, 5)),
-        Operator::Less | Operator::LessOrEqual | Operator::Greater | Operator::GreaterOrEqual | Operator::NotEqual | Operator::EqualEqual => Some((1 , 2)),
-        Operator::And => Some((1, 2)),
-        Operator::Or => Some((0, 1)),
-        _ => None,
+    match level(operator, |fixity| {
+        matches!(fixity, Fixity::Left | Fixity::Right)
+    })? {
+        (power, Fixity::Left) => Some((power, power + 1)),
+        (power, _) => Some((power + 1, power)),
     }
 }
 /// Operator only has one atomic neighbour to its right.
-fn prefix_binding_power(operator: &Operator) -> ((), u8) {
-    match operator {
-        Operator::Plus | Operator::Minus => ((), 5),
-        _ => panic!("Bad prefix operator!"),
-    }
+fn prefix_binding_power(operator: &Operator) -> Option<((), u8)> {
+    level(operator, |fixity| fixity == Fixity::Prefix).map(|(power, _)| ((), power))
 }
 /// Operator only has one atomic neighbour to its left.
 fn postfix_binding_power(operator: &Operator) -> Option<(u8, ())> {
-    match operator {
-        Operator::Bang => Some((7, ())),
-        _ => None,
-    }
+    level(operator, |fixity| fixity == Fixity::Postfix).map(|(power, _)| (power, ()))
 }
 #[cfg(test)]
 mod tests {
-    use crate::parse::Parser;
+    use crate::parse::{ParseError, Parser};
 
     #[test]
     fn expression_print() {
         let parser = Parser::new("1 + 2 * 3");
-        let string = parser.parse();
+        let string = parser.parse().unwrap();
 
         assert_eq!(string.to_string(), "(+ 1 (* 2 3))")
     }
     #[test]
     fn braces() {
-        assert_eq!(Parser::new("((((1 + 2))))").parse().to_string(), "(+ 1 2)")
+        assert_eq!(
+            Parser::new("((((1 + 2))))").parse().unwrap().to_string(),
+            "(+ 1 2)"
+        )
+    }
+    #[test]
+    fn statements() {
+        let program = Parser::new("let a = 1; a + 2;\n a * 3").parse().unwrap();
+
+        assert_eq!(program.statements.len(), 3);
+        assert_eq!(program.to_string(), "(let (= a 1));\n(+ a 2);\n(* a 3)")
+    }
+    #[test]
+    fn compound_assignment() {
+        assert_eq!(
+            Parser::new("a += 1 * 2; b /= 2; c++; --d; -e--")
+                .parse()
+                .unwrap()
+                .to_string(),
+            "(+= a (* 1 2));\n(/= b 2);\n(++ c);\n(-- d);\n(- (-- e))"
+        );
+        let errors = Parser::new("1 += 2; f()++; ++1").parse().err().unwrap();
+
+        assert_eq!(errors.len(), 3);
+        assert!(errors
+            .iter()
+            .all(|error| matches!(error, ParseError::NotAssignable { .. })));
+    }
+    #[test]
+    fn not_and_factorial() {
+        let parse = |input| Parser::new(input).parse().unwrap().to_string();
+
+        assert_eq!(parse("!a == b"), "(== (not a) b)");
+        assert_eq!(parse("5! + 1"), "(+ (! 5) 1)");
+        // Postfix binds tighter, so this is the not of the factorial.
+        assert_eq!(parse("!5!"), "(not (! 5))");
+        assert_eq!(parse("!!a"), "(not (not a))");
+    }
+    #[test]
+    fn lists() {
+        let parse = |input| Parser::new(input).parse().unwrap().to_string();
+
+        assert_eq!(parse("[1, 2 + 3, [],]"), "(list 1 (+ 2 3) (list))");
+        assert_eq!(
+            parse("a[1][i + 1] = -b[0]"),
+            "(= (index (index a 1) (+ i 1)) (- (index b 0)))"
+        );
+        assert_eq!(parse("f()[0]++"), "(++ (index (call f) 0))");
+        assert!(matches!(
+            Parser::new("[1, 2").parse().err().unwrap()[..],
+            [ParseError::UnexpectedEnd { .. }]
+        ));
+    }
+    #[test]
+    fn maps() {
+        let parse = |input| Parser::new(input).parse().unwrap().to_string();
+
+        assert_eq!(
+            parse("{ \"a\": 1, b: 2 + 3, 4: {}, true: [], }"),
+            "(map (a 1) (b (+ 2 3)) (4 (map)) (true (list)))"
+        );
+        assert_eq!(
+            parse("m.a.b = m[\"c\"]"),
+            "(= (index (index m a) b) (index m c))"
+        );
+        assert_eq!(parse("-m.a ** 2"), "(- (** (index m a) 2))");
+        assert_eq!(parse("if {}.a { 1 }"), "(if (index (map) a) : (block 1))");
+
+        // Without a `key:` at the start, it is a block.
+        assert_eq!(parse("{ 1 + 2 }"), "(block (+ 1 2))");
+
+        let errors = Parser::new("{ a: 1, b }; m.1").parse().err().unwrap();
+        assert_eq!(errors.len(), 2);
+        assert!(matches!(
+            &errors[0],
+            ParseError::UnexpectedToken { expected, .. } if expected == "`:`"
+        ));
+        assert!(matches!(
+            &errors[1],
+            ParseError::UnexpectedToken { expected, .. } if expected == "the name of a field"
+        ));
+    }
+    #[test]
+    fn precedence() {
+        let parse = |input| Parser::new(input).parse().unwrap().to_string();
+
+        // Every level against the one above it, from the loosest.
+        assert_eq!(parse("a = b .. c"), "(= a (.. b c))");
+        assert_eq!(parse("a ..= b || c"), "(..= a (|| b c))");
+        assert_eq!(parse("a || b && c"), "(|| a (&& b c))");
+        assert_eq!(parse("a && b == c"), "(&& a (== b c))");
+        assert_eq!(parse("a == b < c"), "(== a (< b c))");
+        assert_eq!(parse("a < b | c"), "(< a (| b c))");
+        assert_eq!(parse("a | b ^ c"), "(| a (^ b c))");
+        assert_eq!(parse("a ^ b & c"), "(^ a (& b c))");
+        assert_eq!(parse("a & b << c"), "(& a (<< b c))");
+        assert_eq!(parse("a << b + c"), "(<< a (+ b c))");
+        assert_eq!(parse("a + b * c"), "(+ a (* b c))");
+        assert_eq!(parse("-a * b"), "(* (- a) b)");
+        assert_eq!(parse("-a ** b"), "(- (** a b))");
+        assert_eq!(parse("a ** b!"), "(** a (! b))");
+        assert_eq!(parse("a!(b)"), "(call (! a) b)");
+        // Associativity.
+        assert_eq!(parse("a = b = c"), "(= a (= b c))");
+        assert_eq!(parse("a += b -= 1"), "(+= a (-= b 1))");
+        assert_eq!(parse("a - b - c"), "(- (- a b) c)");
+        assert_eq!(parse("a == b != c"), "(!= (== a b) c)");
+        assert_eq!(parse("a ** b ** c"), "(** a (** b c))");
+        // The ones from the bug report.
+        assert_eq!(parse("a = b == c && d"), "(= a (&& (== b c) d))");
+        assert_eq!(parse("a < b && c < d"), "(&& (< a b) (< c d))");
+    }
+    #[test]
+    fn else_if() {
+        let parse = |input| Parser::new(input).parse().unwrap().to_string();
+
+        assert_eq!(
+            parse("if a { 1 } else if b { 2 } else if c { 3 } else { 4 }"),
+            "(if a : (block 1) ? (if b : (block 2) ? (if c : (block 3) ? (block 4))))"
+        );
+        assert_eq!(
+            parse("if a { 1 } else if b { 2 }"),
+            "(if a : (block 1) ? (if b : (block 2)))"
+        );
+        assert_eq!(
+            parse("let x = if a { 1 } else { 2 };"),
+            "(let (= x (if a : (block 1) ? (block 2))));"
+        );
+        let errors = Parser::new("if a { 1 } else b").parse().err().unwrap();
+        assert!(matches!(
+            &errors[..],
+            [ParseError::UnexpectedToken { expected, .. }] if expected == "`{`"
+        ));
+    }
+    #[test]
+    fn for_loops() {
+        let parse = |input| Parser::new(input).parse().unwrap().to_string();
+
+        assert_eq!(
+            parse("for i in 0..n + 1 { i } 1"),
+            "(for i (.. 0 (+ n 1)) : (block i))\n1"
+        );
+        assert_eq!(
+            parse("for k in { a: 1 } { k }"),
+            "(for k (map (a 1)) : (block k))"
+        );
+        let errors = Parser::new("for 1 in a { }; for i a { }")
+            .parse()
+            .err()
+            .unwrap();
+        assert!(matches!(
+            &errors[..],
+            [ParseError::UnexpectedToken { expected: first, .. }, ParseError::UnexpectedToken { expected: second, .. }]
+                if first == "the name of the loop variable" && second == "`in`"
+        ));
+    }
+    #[test]
+    fn labeled_loops() {
+        let parse = |input| Parser::new(input).parse().unwrap().to_string();
+
+        assert_eq!(
+            parse("'outer: for i in a { while b { break 'outer } }"),
+            "(for 'outer i a : (block (while b : (block (break 'outer)))))"
+        );
+        assert_eq!(
+            parse("while a { continue }"),
+            "(while a : (block (continue)))"
+        );
+    }
+    #[test]
+    fn jumps_outside_of_loops() {
+        let errors = Parser::new(
+            "break; while a { || continue }; 'a: while b { break 'c }; fn f() { break } 'a: 1",
+        )
+        .parse()
+        .err()
+        .unwrap();
+
+        assert!(matches!(
+            &errors[0],
+            ParseError::OutsideLoop { keyword, bad_bit, .. } if keyword == "break" && bad_bit.offset() == 0
+        ));
+        assert!(matches!(
+            &errors[1],
+            ParseError::OutsideLoop { keyword, .. } if keyword == "continue"
+        ));
+        assert!(matches!(
+            &errors[2],
+            ParseError::UnknownLabel { label, bad_bit, .. } if label == "c" && bad_bit.len() == 8
+        ));
+        assert!(matches!(&errors[3], ParseError::OutsideLoop { .. }));
+        assert!(matches!(
+            &errors[4],
+            ParseError::UnexpectedToken { expected, .. } if expected == "a loop after the label"
+        ));
+        assert_eq!(errors.len(), 5);
+    }
+    #[test]
+    fn arithmetic_and_bitwise() {
+        let parse = |input| Parser::new(input).parse().unwrap().to_string();
+
+        assert_eq!(parse("2 ** 3 ** 2"), "(** 2 (** 3 2))");
+        assert_eq!(parse("-2 ** 2 * 3"), "(* (- (** 2 2)) 3)");
+        assert_eq!(parse("2 ** -1"), "(** 2 (- 1))");
+        assert_eq!(parse("a + b % c ~/ d"), "(+ a (~/ (% b c) d))");
+        assert_eq!(
+            parse("a | b ^ c & d << 1 + 2"),
+            "(| a (^ b (& c (<< d (+ 1 2)))))"
+        );
+        assert_eq!(parse("~a >> 2!"), "(>> (~ a) (! 2))");
+        assert_eq!(parse("a & 1 == 0"), "(== (& a 1) 0)");
+    }
+    #[test]
+    fn blocks() {
+        let parse = |input| Parser::new(input).parse().unwrap().to_string();
+
+        assert_eq!(
+            parse("if c { a = 1; b = 2; } else { a; ; b }"),
+            "(if c : (block (= a 1); (= b 2);) ? (block a; b))"
+        );
+        assert_eq!(parse("while a { }"), "(while a : (block))");
+        assert_eq!(
+            parse("let x = { let y = 1; y + 1 }; { x } x"),
+            "(let (= x (block (let (= y 1)); (+ y 1))));\n(block x)\nx"
+        );
+        assert_eq!(parse("|| { 1; 2 }"), "(fn () : (block 1; 2))");
+        // A block statement ends at its `}`.
+        assert_eq!(
+            parse("let a = 0; { a = 1; }\n[1, 2]"),
+            "(let (= a 0));\n(block (= a 1);)\n(list 1 2)"
+        );
+        assert_eq!(parse("{ a }\n(b)"), "(block a)\nb");
+        let errors = Parser::new("fn f() { a b }").parse().err().unwrap();
+        assert!(matches!(
+            &errors[0],
+            ParseError::UnexpectedToken { expected, found, .. } if expected == "`;`" && found == "`b`"
+        ));
+    }
+    #[test]
+    fn statements_after_block() {
+        let program = Parser::new("if a { 1 } while b { 2 } 3;").parse().unwrap();
+
+        assert_eq!(program.statements.len(), 3);
+    }
+    #[test]
+    fn block_statements_end() {
+        let parse = |input| Parser::new(input).parse().unwrap().to_string();
+
+        // The next line is a new statement, not a call, index or subtraction.
+        assert_eq!(
+            parse("let a = 1; if a == 1 { a = 2 }\n(a)"),
+            "(let (= a 1));\n(if (== a 1) : (block (= a 2)))\na"
+        );
+        assert_eq!(
+            parse("while true { break } - 1"),
+            "(while true : (block (break)))\n(- 1)"
+        );
+        assert_eq!(parse("fn f() { 1 } (2)"), "(fn f () : (block 1))\n2");
+        assert_eq!(
+            parse("for i in a { i } [1]"),
+            "(for i a : (block i))\n(list 1)"
+        );
+        // Inside of an expression, they are still operands.
+        assert_eq!(
+            parse("let b = if a { 1 } else { 2 } - 1;"),
+            "(let (= b (- (if a : (block 1) ? (block 2)) 1)));"
+        );
+        assert_eq!(parse("fn(x) { x }(2)"), "(call (fn (x) : (block x)) 2)");
+        assert_eq!(parse("{ a: 1 }.a"), "(index (map (a 1)) a)");
+    }
+    #[test]
+    fn functions() {
+        assert_eq!(
+            Parser::new("fn add(a, b,) { return a + b } add(1, 2 * 3)(4)() + -f()!")
+                .parse()
+                .unwrap()
+                .to_string(),
+            "(fn add (a b) : (block (return (+ a b))))\n(+ (call (call (call add 1 (* 2 3)) 4)) (- (! (call f))))"
+        );
+        assert_eq!(
+            Parser::new("fn nothing() { return }")
+                .parse()
+                .unwrap()
+                .to_string(),
+            "(fn nothing () : (block (return)))"
+        );
+    }
+    #[test]
+    fn closures() {
+        assert_eq!(
+            Parser::new("let f = |a, b,| a + b; fn(x) { x }(1); || 1")
+                .parse()
+                .unwrap()
+                .to_string(),
+            "(let (= f (fn (a b) : (+ a b))));\n(call (fn (x) : (block x)) 1);\n(fn () : 1)"
+        );
+    }
+    #[test]
+    fn bad_parameters() {
+        let errors = Parser::new("fn f(a b) { 1 }; fn 1(a) { 2 }")
+            .parse()
+            .err()
+            .unwrap();
+
+        assert_eq!(errors.len(), 2);
+        assert!(
+            matches!(&errors[0], ParseError::UnexpectedToken { expected, .. } if expected == "`)`")
+        );
+        assert!(matches!(
+            &errors[1],
+            ParseError::UnexpectedToken { expected, .. } if expected == "the name of the function"
+        ));
+    }
+    #[test]
+    fn missing_brace() {
+        let errors = Parser::new("if a { 1 ").parse().err().unwrap();
+
+        assert!(matches!(errors[..], [ParseError::UnexpectedEnd { .. }]));
+    }
+    #[test]
+    fn recovers_at_statements() {
+        let errors = Parser::new("let a = ); let b = 2; 1 = b; while ) { }")
+            .parse()
+            .err()
+            .unwrap();
+
+        assert_eq!(errors.len(), 3);
+        assert!(matches!(
+            &errors[0],
+            ParseError::UnexpectedToken { found, bad_bit, .. } if found == "`)`" && bad_bit.offset() == 8
+        ));
+        assert!(
+            matches!(&errors[1], ParseError::NotAssignable { bad_bit, .. } if bad_bit.offset() == 22)
+        );
+        assert!(matches!(&errors[2], ParseError::UnexpectedToken { .. }));
+        // In a block, the rest of the block, and the code after it, are parsed normally.
+        let errors = Parser::new("fn f() { let a = ); let b = 1; } let c = 2; c = ;")
+            .parse()
+            .err()
+            .unwrap();
+
+        assert_eq!(errors.len(), 2);
+        assert!(matches!(
+            &errors[0],
+            ParseError::UnexpectedToken { found, bad_bit, .. } if found == "`)`" && bad_bit.offset() == 17
+        ));
+        assert!(matches!(
+            &errors[1],
+            ParseError::UnexpectedToken { found, .. } if found == "`;`"
+        ));
+        // The blocks inside of the broken statement are skipped whole.
+        let errors = Parser::new("let a = ) { let b = 1; }; let c = 2;")
+            .parse()
+            .err()
+            .unwrap();
+        assert_eq!(errors.len(), 1);
+    }
+    #[test]
+    fn lexer_errors() {
+        let errors = Parser::new("let a = 1 # 2; let b = @;")
+            .parse()
+            .err()
+            .unwrap();
+
+        assert_eq!(errors.len(), 2);
+        assert!(errors
+            .iter()
+            .all(|error| matches!(error, ParseError::Lexer(_))));
     }
 }